@@ -0,0 +1,255 @@
+//! Markdown and org-mode checklists, for pasting a day's plan into notes and
+//! reading it back. Each day gets a heading, and each task a line like
+//! `- [x] 9:00 standup`, with its subtasks indented below it.
+
+use anyhow::Context;
+use chrono::{DateTime, Local, NaiveDate, TimeZone};
+
+use crate::{
+    cli::split_desc,
+    data::{Deadline, ImportedTask, Keeper, Priority, Task, ALL_DAY_HOUR},
+};
+
+const DATE: &str = "%Y-%m-%d";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flavor {
+    Markdown,
+    Org,
+}
+
+impl Flavor {
+    fn heading(&self, date: NaiveDate) -> String {
+        match self {
+            Self::Markdown => format!("## {}", date.format("%d %b %Y")),
+            Self::Org => format!("* <{}>", date.format("%Y-%m-%d %a")),
+        }
+    }
+
+    /// The text of a heading, if `line` is one.
+    fn strip_heading<'a>(&self, line: &'a str) -> Option<&'a str> {
+        let marker = match self {
+            Self::Markdown => '#',
+            Self::Org => '*',
+        };
+        let text = line.trim_start_matches(marker);
+        (text.len() < line.len() && text.starts_with(' ')).then(|| text.trim())
+    }
+
+    fn checkbox(&self, done: bool) -> &'static str {
+        match (self, done) {
+            (_, false) => "[ ]",
+            (Self::Markdown, true) => "[x]",
+            (Self::Org, true) => "[X]",
+        }
+    }
+}
+
+/// Writes every day from `from` to `to` (inclusive), or if either end is
+/// open, just the days with tasks in that direction.
+pub fn export(
+    keeper: &Keeper,
+    flavor: Flavor,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+) -> String {
+    let dates: Vec<_> = match (from, to) {
+        (Some(from), Some(to)) => from.iter_days().take_while(|date| *date <= to).collect(),
+        _ => keeper
+            .days
+            .keys()
+            .filter(|date| from.is_none_or(|from| **date >= from))
+            .filter(|date| to.is_none_or(|to| **date <= to))
+            .copied()
+            .collect(),
+    };
+
+    let mut out = String::new();
+    for (i, date) in dates.into_iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        out.push_str(&flavor.heading(date));
+        out.push_str("\n\n");
+        let timeslots = keeper
+            .days
+            .get(&date)
+            .map(|schedule| &schedule.timeslots)
+            .filter(|timeslots| !timeslots.is_empty());
+        let Some(timeslots) = timeslots else {
+            out.push_str("Empty\n");
+            continue;
+        };
+        for (hour, tasks) in timeslots {
+            for task in tasks {
+                item(&mut out, flavor, task, Some(*hour), 0);
+            }
+        }
+    }
+    out
+}
+
+fn item(out: &mut String, flavor: Flavor, task: &Task, hour: Option<usize>, indent: usize) {
+    let mut words = vec![flavor.checkbox(task.completed()).to_string()];
+    words.extend(hour.map(|hour| format!("{hour}:00")));
+    words.push(task.desc().to_string());
+    match task.priority() {
+        Priority::Normal => {}
+        priority => words.push(format!("!{priority:?}").to_lowercase()),
+    }
+    words.extend(task.tags().iter().cloned());
+    if let Some(deadline) = task.deadline() {
+        words.push(format!("due:{}", deadline.date.format(DATE)));
+        words.extend(deadline.hour.map(|hour| format!("duetime:{hour}:00")));
+    }
+    out.push_str(&format!("{:indent$}- {}\n", "", words.join(" ")));
+    for subtask in task.subtasks() {
+        item(out, flavor, subtask, None, indent + 2);
+    }
+}
+
+/// A checklist item whose subtasks are still being read.
+struct Open {
+    indent: usize,
+    task: Task,
+    done: bool,
+    slot: (NaiveDate, usize),
+}
+
+/// Reads the checklists in a note. Items go on the day of the heading above
+/// them, or on `date` if there isn't one, and at the hour they start with.
+/// Everything else in the note is skipped.
+pub fn import(
+    text: &str,
+    flavor: Flavor,
+    mut date: NaiveDate,
+    now: DateTime<Local>,
+) -> anyhow::Result<Vec<ImportedTask>> {
+    let mut tasks = Vec::new();
+    let mut open: Vec<Open> = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        if let Some(heading) = flavor.strip_heading(line) {
+            close(&mut open, &mut tasks, 0, now);
+            date = parse_heading(heading).unwrap_or(date);
+            continue;
+        }
+        let Some((indent, done, rest)) = parse_item(line) else {
+            continue;
+        };
+        close(&mut open, &mut tasks, indent, now);
+
+        let mut words: Vec<_> = rest.split_whitespace().collect();
+        let hour = words.first().and_then(|word| parse_hour(word));
+        if hour.is_some() {
+            words.remove(0);
+        }
+        let task =
+            parse_task(&words).with_context(|| format!("invalid item on line {}", number + 1))?;
+        // Subtasks go in their parent's hour, whatever they say
+        let slot = match open.last() {
+            Some(parent) => parent.slot,
+            None => (date, hour.unwrap_or(ALL_DAY_HOUR)),
+        };
+        open.push(Open {
+            indent,
+            task,
+            done,
+            slot,
+        });
+    }
+    close(&mut open, &mut tasks, 0, now);
+    Ok(tasks)
+}
+
+/// Finishes the open items indented at least `indent`, attaching them to
+/// their parents.
+fn close(open: &mut Vec<Open>, tasks: &mut Vec<ImportedTask>, indent: usize, now: DateTime<Local>) {
+    while open.last().is_some_and(|item| item.indent >= indent) {
+        let Open {
+            mut task,
+            done,
+            slot: (date, hour),
+            ..
+        } = open.pop().unwrap();
+        if done {
+            // Like tasks from before completion times were recorded
+            let end = date.and_hms_opt(hour as u32, 59, 59).unwrap();
+            let at = Local.from_local_datetime(&end).earliest().unwrap_or(now);
+            // Not `mark_complete`, which would check off unchecked items
+            // underneath
+            task.set_completed_at(Some(at.min(now)));
+        }
+        match open.last_mut() {
+            Some(parent) => parent.task.add_subtask(task),
+            None => tasks.push(ImportedTask {
+                task,
+                slot: Some((date, hour)),
+                parent: None,
+            }),
+        }
+    }
+}
+
+/// The indent, whether it's checked, and the rest of a checklist item.
+fn parse_item(line: &str) -> Option<(usize, bool, &str)> {
+    let trimmed = line.trim_start();
+    let indent = line[..line.len() - trimmed.len()]
+        .chars()
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum();
+    let rest = ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|bullet| trimmed.strip_prefix(bullet))?
+        .trim_start();
+    let done = match rest.get(..3)? {
+        "[ ]" | "[-]" => false,
+        "[x]" | "[X]" => true,
+        _ => return None,
+    };
+    Some((indent, done, rest[3..].trim()))
+}
+
+fn parse_task(words: &[&str]) -> anyhow::Result<Task> {
+    let mut desc = Vec::new();
+    let (mut due, mut due_hour) = (None, None);
+    for word in words {
+        match word.split_once(':') {
+            Some(("due", value)) => {
+                due = Some(
+                    NaiveDate::parse_from_str(value, DATE)
+                        .with_context(|| format!("invalid date '{value}'"))?,
+                )
+            }
+            Some(("duetime", value)) => due_hour = parse_hour(value),
+            _ => desc.push(*word),
+        }
+    }
+    let (desc, priority, tags) = split_desc(&desc.join(" "));
+    anyhow::ensure!(!desc.is_empty(), "no description");
+    let mut task = Task::with_priority(desc, priority.unwrap_or_default());
+    for tag in tags {
+        task.add_tag(&tag);
+    }
+    task.set_deadline(due.map(|date| Deadline {
+        date,
+        hour: due_hour,
+    }));
+    Ok(task)
+}
+
+/// Parses an hour like `9:00` or `14:30`. Minutes are dropped.
+fn parse_hour(word: &str) -> Option<usize> {
+    let (hour, minutes) = word.split_once(':')?;
+    if minutes.len() != 2 || !minutes.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    hour.parse().ok().filter(|hour| *hour < 24)
+}
+
+/// Headings like `01 Feb 2024`, `2024-02-01` or org's `<2024-02-01 Thu>`.
+fn parse_heading(heading: &str) -> Option<NaiveDate> {
+    let heading = heading.trim_matches(['<', '>', '[', ']']);
+    ["%d %b %Y", "%a %d %b %Y", "%Y-%m-%d %a", DATE]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(heading, format).ok())
+}
@@ -1,9 +1,19 @@
-use chrono::{Local, NaiveDate};
+use crate::{
+    data::{Deadline, Priority},
+    filter::{is_tag, Expr, Filter},
+    output::OutputFormat,
+    search::{Pattern, Search, Status},
+    stats::Period,
+    statusbar::Bar,
+};
+use chrono::NaiveDate;
 use keeper_util::{
+    clock::Clock,
     color::{GREEN, RESET, YELLOW},
-    current_version, error, fatal, parse_date,
+    current_version, error, exit, fatal, parse_date,
 };
-use std::{env::Args, process};
+use regex::RegexBuilder;
+use std::path::PathBuf;
 
 #[derive(Debug, Clone, Copy)]
 pub enum ShowSet {
@@ -11,17 +21,62 @@ pub enum ShowSet {
     Date(NaiveDate),
 }
 
+/// A file format other programs use, for `import` and `export`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileFormat {
+    Ics,
+    TodoTxt,
+    Markdown,
+    Org,
+}
+
+impl FileFormat {
+    /// Whether this is a checklist of days, rather than a list of tasks.
+    fn checklist(&self) -> bool {
+        matches!(self, Self::Markdown | Self::Org)
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "ics" | "ical" | "icalendar" => Some(Self::Ics),
+            "todo.txt" | "todotxt" | "txt" => Some(Self::TodoTxt),
+            "md" | "markdown" => Some(Self::Markdown),
+            "org" => Some(Self::Org),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum Command {
     Add {
         date: NaiveDate,
         hour: usize,
         desc: String,
+        priority: Priority,
+        tags: Vec<String>,
+        deadline: Option<Deadline>,
+        /// Path to the parent task when adding a subtask.
+        parent: Option<Vec<usize>>,
+    },
+    InboxAdd {
+        desc: String,
+        priority: Priority,
+        tags: Vec<String>,
+        deadline: Option<Deadline>,
+    },
+    InboxList {
+        filter: Filter,
+    },
+    Schedule {
+        index: usize,
+        date: NaiveDate,
+        hour: usize,
     },
     Mark {
         date: NaiveDate,
         hour: usize,
-        index: usize,
+        path: Vec<usize>,
     },
     Change {
         date: NaiveDate,
@@ -29,11 +84,104 @@ pub enum Command {
         index: usize,
         new_hour: usize,
     },
+    Delete {
+        date: NaiveDate,
+        hour: usize,
+        path: Vec<usize>,
+    },
+    DeleteInbox {
+        index: usize,
+    },
     Show {
         set: ShowSet,
+        filter: Filter,
+        verbose: bool,
+        /// Machine-readable output instead of colored text.
+        format: Option<OutputFormat>,
     },
     Render {
         set: ShowSet,
+        filter: Filter,
+    },
+    List {
+        filter: Filter,
+    },
+    Search {
+        search: Search,
+        format: Option<OutputFormat>,
+    },
+    History {
+        set: ShowSet,
+        filter: Filter,
+        format: Option<OutputFormat>,
+    },
+    Stats {
+        days: usize,
+        period: Period,
+        /// Where to save a chart, if anywhere.
+        chart: Option<PathBuf>,
+        format: Option<OutputFormat>,
+    },
+    Export {
+        format: FileFormat,
+        /// Where to write, or `None` for stdout.
+        path: Option<PathBuf>,
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+        /// Write calendar events instead of todos.
+        events: bool,
+    },
+    Import {
+        format: FileFormat,
+        /// Where to read from, `-` for stdin.
+        path: PathBuf,
+        /// The day for checklist items without a date heading.
+        date: Option<NaiveDate>,
+    },
+    Daemon,
+    Notify,
+    Status {
+        bar: Bar,
+        /// Keep printing a new line whenever it changes.
+        watch: bool,
+    },
+    Tui,
+    Batch {
+        /// Where to read commands from, `-` for stdin.
+        path: PathBuf,
+    },
+    Repl,
+    Snooze {
+        date: NaiveDate,
+        hour: usize,
+        index: usize,
+        minutes: u32,
+    },
+    Remind {
+        date: NaiveDate,
+        hour: usize,
+        index: usize,
+        minutes: u32,
+    },
+    Note {
+        date: NaiveDate,
+        hour: usize,
+        path: Vec<usize>,
+    },
+    Due {
+        date: NaiveDate,
+        hour: usize,
+        path: Vec<usize>,
+        deadline: Option<Deadline>,
+    },
+    Depend {
+        date: NaiveDate,
+        hour: usize,
+        path: Vec<usize>,
+        on_date: NaiveDate,
+        on_hour: usize,
+        on_path: Vec<usize>,
+        remove: bool,
     },
 }
 
@@ -47,126 +195,444 @@ keeper-todo ({version}) Felix Prasanna 2024
     keeper-todo help
 {YELLOW}add{RESET}:
     keeper-todo add {GREEN}date{RESET} hour desc
+    keeper-todo add {GREEN}date{RESET} hour desc -p {GREEN}priority{RESET}
+    keeper-todo add {GREEN}date{RESET} hour desc --tag {GREEN}tag{RESET} ...
+    keeper-todo add {GREEN}date{RESET} hour desc --due {GREEN}deadline{RESET}
+    keeper-todo add {GREEN}date{RESET} desc --parent hour.index
+{YELLOW}history{RESET}:
+    keeper-todo history {GREEN}date{RESET}
+    keeper-todo history {GREEN}count{RESET}
+    keeper-todo history
+    keeper-todo history --priority {GREEN}priority{RESET} --tag {GREEN}filter{RESET} ...
+    keeper-todo history --format {GREEN}output{RESET} ...
+{YELLOW}stats{RESET}:
+    keeper-todo stats
+    keeper-todo stats {GREEN}count{RESET} --by {GREEN}period{RESET}
+    keeper-todo stats {GREEN}count{RESET} --chart path
+    keeper-todo stats {GREEN}count{RESET} --format {GREEN}output{RESET}
+{YELLOW}inbox{RESET}:
+    keeper-todo inbox add desc
+    keeper-todo inbox add desc -p {GREEN}priority{RESET} --tag {GREEN}tag{RESET} --due {GREEN}deadline{RESET}
+    keeper-todo inbox list
+    keeper-todo inbox list --priority {GREEN}priority{RESET} --tag {GREEN}filter{RESET}
+{YELLOW}schedule{RESET}:
+    keeper-todo schedule index {GREEN}date{RESET} hour
 {YELLOW}mark{RESET}:
     keeper-todo mark {GREEN}date{RESET} hour.index
+    keeper-todo mark {GREEN}date{RESET} hour.index.subtask...
     keeper-todo mark {GREEN}date{RESET} hour
 {YELLOW}change{RESET}:
     keeper-todo change {GREEN}date{RESET} hour.index new-hour
     keeper-todo change {GREEN}date{RESET} hour new-hour
+{YELLOW}delete{RESET}:
+    keeper-todo delete {GREEN}date{RESET} hour.index
+    keeper-todo delete {GREEN}date{RESET} hour.index.subtask...
+    keeper-todo delete inbox index
 {YELLOW}show{RESET}:
     keeper-todo show {GREEN}date{RESET}
     keeper-todo show {GREEN}count{RESET}
     keeper-todo show
+    keeper-todo show --priority {GREEN}priority{RESET} ...
+    keeper-todo show --tag {GREEN}filter{RESET} ...
+    keeper-todo show --verbose ...
+    keeper-todo show --format {GREEN}output{RESET} ...
 {YELLOW}render{RESET}:
     keeper-todo render {GREEN}date{RESET} path
     keeper-todo render {GREEN}count{RESET} path
+    keeper-todo render --tag {GREEN}filter{RESET} ...
+{YELLOW}list{RESET}:
+    keeper-todo list
+    keeper-todo list --priority {GREEN}priority{RESET} --tag {GREEN}filter{RESET}
+{YELLOW}search{RESET}:
+    keeper-todo search query
+    keeper-todo search query --regex
+    keeper-todo search query --fuzzy
+    keeper-todo search query --status {GREEN}status{RESET} --from {GREEN}date{RESET} --to {GREEN}date{RESET}
+    keeper-todo search query --priority {GREEN}priority{RESET} --tag {GREEN}filter{RESET}
+    keeper-todo search query --format {GREEN}output{RESET}
+{YELLOW}note{RESET}:
+    keeper-todo note {GREEN}date{RESET} hour.index
+    keeper-todo note {GREEN}date{RESET} hour.index.subtask...
+{YELLOW}due{RESET}:
+    keeper-todo due {GREEN}date{RESET} hour.index {GREEN}deadline{RESET}
+    keeper-todo due {GREEN}date{RESET} hour.index none
+{YELLOW}depend{RESET}:
+    keeper-todo depend {GREEN}date{RESET} hour.index {GREEN}date{RESET} hour.index
+    keeper-todo undepend {GREEN}date{RESET} hour.index {GREEN}date{RESET} hour.index
+{YELLOW}export{RESET}:
+    keeper-todo export {GREEN}format{RESET}
+    keeper-todo export {GREEN}format{RESET} path --from {GREEN}date{RESET} --to {GREEN}date{RESET}
+    keeper-todo export ics path --events
+    keeper-todo export (md|org)
+    keeper-todo export (md|org) {GREEN}date{RESET} path
+    keeper-todo export (md|org) count path
+{YELLOW}import{RESET}:
+    keeper-todo import {GREEN}format{RESET} path
+    keeper-todo import (md|org) path --date {GREEN}date{RESET}
+{YELLOW}daemon{RESET}:
+    keeper-todo daemon
+{YELLOW}notify{RESET}:
+    keeper-todo notify
+{YELLOW}status{RESET}:
+    keeper-todo status
+    keeper-todo status {GREEN}bar{RESET}
+    keeper-todo status {GREEN}bar{RESET} --watch
+{YELLOW}tui{RESET}:
+    keeper-todo tui
+{YELLOW}batch{RESET}:
+    keeper-todo batch
+    keeper-todo batch path
+{YELLOW}repl{RESET}:
+    keeper-todo repl
+{YELLOW}snooze{RESET}:
+    keeper-todo snooze {GREEN}date{RESET} hour.index minutes
+{YELLOW}remind{RESET}:
+    keeper-todo remind {GREEN}date{RESET} hour.index minutes
 
 {YELLOW}terms{RESET}:
-    date = {GREEN}(dd-mm-yy|today|tomorrow|yesterday){RESET}"
+    date = {GREEN}(dd-mm-yy|today|tomorrow|yesterday){RESET}
+    priority = {GREEN}(urgent|high|normal|low|1|2|3|4){RESET}
+    period = {GREEN}(day|week|month){RESET}
+    status = {GREEN}(done|todo|overdue|blocked){RESET}
+    format = {GREEN}(ics|todo.txt|md|org){RESET}
+    output = {GREEN}(json|ron|csv){RESET}
+    bar = {GREEN}(plain|waybar|polybar|i3blocks|tmux){RESET}
+    deadline = {GREEN}date{RESET}, or {GREEN}\"date hour\"{RESET} to be due by the end of that hour
+    filter = {GREEN}+project, @context, and, or, not, (...){RESET}
+    desc can also set the priority with a word like {GREEN}!high{RESET}
+    desc can also add tags with words like {GREEN}+project{RESET} and {GREEN}@context{RESET}"
     );
-    process::exit(0);
+    exit(0);
+}
+
+/// Parses a task id of the form [hour.index], or [hour] for the first task in
+/// that hour.
+fn parse_id(command: &str, id: Option<String>) -> (usize, usize) {
+    let (hour, path) = parse_path(command, id);
+    if path.len() > 1 {
+        fatal!("{command} doesn't work on subtasks");
+    }
+    (hour, path[0])
+}
+
+/// Parses a task id that may point at a subtask, of the form
+/// [hour.index.subtask...]. Returns the hour and the path of indices, which is
+/// never empty.
+fn parse_path(command: &str, id: Option<String>) -> (usize, Vec<usize>) {
+    let Some(id) = id else {
+        error!("no id provided to {command}");
+        fatal!("expecting format [hour.index] or [hour]");
+    };
+    let mut parts = id.split('.');
+    let Ok(hour) = parts.next().unwrap_or_default().parse() else {
+        fatal!("failed to parse hour from format [hour.index]");
+    };
+    if !(0..24).contains(&hour) {
+        fatal!("hour [{}] is not in 0..24", hour);
+    }
+    let mut path = Vec::new();
+    for index in parts {
+        let Ok(index) = index.parse() else {
+            fatal!("failed to parse index from format [hour.index]");
+        };
+        path.push(index);
+    }
+    if path.is_empty() {
+        path.push(0);
+    }
+    (hour, path)
+}
+
+fn parse_hour(command: &str, hour: Option<String>) -> usize {
+    let Some(hour) = hour else {
+        fatal!("no hour provided to {command}");
+    };
+    let Ok(hour) = hour.parse() else {
+        fatal!("failed to parse hour");
+    };
+    if !(0..24).contains(&hour) {
+        fatal!("hour [{}] is not in 0..24", hour);
+    }
+    hour
+}
+
+/// Parses a deadline of the form [date] or [date hour].
+fn parse_deadline(due: &str, clock: Clock) -> Deadline {
+    let mut parts = due.split_whitespace();
+    let Some(date) = parts.next() else {
+        fatal!("no date provided for the deadline");
+    };
+    let date = parse_date(date, clock);
+    let hour = parts
+        .next()
+        .map(|hour| parse_hour("--due", Some(hour.to_string())));
+    if let Some(part) = parts.next() {
+        fatal!("unexpected [{part}] in deadline [{due}]");
+    }
+    Deadline { date, hour }
+}
+
+fn parse_minutes(command: &str, minutes: Option<String>) -> u32 {
+    let Some(minutes) = minutes else {
+        fatal!("no minutes provided to {command}");
+    };
+    let Ok(minutes) = minutes.parse() else {
+        fatal!("failed to parse minutes from [{minutes}]");
+    };
+    minutes
+}
+
+fn parse_priority(priority: &str) -> Priority {
+    let Some(priority) = Priority::parse(priority) else {
+        fatal!("failed to parse priority from [{priority}]");
+    };
+    priority
+}
+
+/// Whether `s` is something [`parse_date`] understands.
+fn is_date(s: &str) -> bool {
+    matches!(s, "today" | "tomorrow" | "yesterday")
+        || NaiveDate::parse_from_str(s, "%d-%m-%Y").is_ok()
+}
+
+fn parse_format(command: &str, format: Option<String>) -> FileFormat {
+    let Some(format) = format else {
+        fatal!("no format provided to {command}");
+    };
+    let Some(format) = FileFormat::parse(&format) else {
+        fatal!("unknown format [{format}]");
+    };
+    format
+}
+
+/// Removes `flag` and the value after it from `args`, returning the value.
+fn take_flag(args: &mut Vec<String>, flags: &[&str]) -> Option<String> {
+    let i = args.iter().position(|arg| flags.contains(&arg.as_str()))?;
+    args.remove(i);
+    if i == args.len() {
+        fatal!("no value provided to {}", flags[0]);
+    }
+    Some(args.remove(i))
+}
+
+/// Removes `flag` from `args`, returning whether it was there.
+fn take_switch(args: &mut Vec<String>, flags: &[&str]) -> bool {
+    let before = args.len();
+    args.retain(|arg| !flags.contains(&arg.as_str()));
+    args.len() != before
+}
+
+/// Removes the `--format` flag from `args`.
+fn take_output(args: &mut Vec<String>) -> Option<OutputFormat> {
+    take_flag(args, &["--format"]).map(|format| {
+        OutputFormat::parse(&format).unwrap_or_else(|| {
+            fatal!("failed to parse output format from [{format}]");
+        })
+    })
+}
+
+/// Removes the `--priority` and `--tag` flags from `args`.
+fn take_filter(args: &mut Vec<String>) -> Filter {
+    let priority = take_flag(args, &["--priority", "-p"]).map(|p| parse_priority(&p));
+    let tags = take_flag(args, &["--tag", "-t"]).map(|expr| {
+        Expr::parse(&expr).unwrap_or_else(|e| {
+            fatal!("{e}");
+        })
+    });
+    Filter { priority, tags }
+}
+
+/// Removes every `--tag` flag from `args`, returning the tags.
+fn take_tags(args: &mut Vec<String>) -> Vec<String> {
+    let mut tags = Vec::new();
+    while let Some(tag) = take_flag(args, &["--tag", "-t"]) {
+        // Projects are the more common kind of tag
+        match is_tag(&tag) {
+            true => tags.push(tag),
+            false => tags.push(format!("+{tag}")),
+        }
+    }
+    tags
+}
+
+/// Works out a new task's description, priority and tags from its desc
+/// argument and the `--priority`/`--tag` flags that were given.
+fn parse_desc(
+    command: &str,
+    desc: Option<String>,
+    flag: Option<String>,
+    mut tags: Vec<String>,
+) -> (String, Priority, Vec<String>) {
+    let Some(desc) = desc else {
+        fatal!("no desc provided to {command}");
+    };
+    let (desc, word, words) = split_desc(&desc);
+    tags.extend(words);
+    if desc.is_empty() {
+        fatal!("no desc provided to {command}");
+    }
+    let priority = flag
+        .map(|p| parse_priority(&p))
+        .or(word)
+        .unwrap_or_default();
+    (desc, priority, tags)
+}
+
+/// Pulls priority words like `!high` and tags like `+project` or `@context`
+/// out of a description.
+pub(crate) fn split_desc(desc: &str) -> (String, Option<Priority>, Vec<String>) {
+    let mut priority = None;
+    let mut tags = Vec::new();
+    let words: Vec<_> = desc
+        .split(' ')
+        .filter(|word| {
+            if is_tag(word) {
+                tags.push(word.to_string());
+                return false;
+            }
+            match word.strip_prefix('!').and_then(Priority::parse) {
+                Some(p) => priority = Some(p),
+                None => return true,
+            }
+            false
+        })
+        .collect();
+    (words.join(" "), priority, tags)
 }
 
 impl Command {
-    pub fn parse(args: Args) -> Self {
+    /// Whether this changes tasks, as opposed to only looking at them (or at
+    /// reminder state, which isn't committed).
+    pub fn changes_data(&self) -> bool {
+        match self {
+            Self::Add { .. }
+            | Self::InboxAdd { .. }
+            | Self::Schedule { .. }
+            | Self::Mark { .. }
+            | Self::Change { .. }
+            | Self::Delete { .. }
+            | Self::DeleteInbox { .. }
+            | Self::Import { .. }
+            | Self::Remind { .. }
+            | Self::Depend { .. }
+            | Self::Due { .. }
+            | Self::Note { .. }
+            | Self::Tui
+            | Self::Batch { .. }
+            | Self::Repl => true,
+            Self::InboxList { .. }
+            | Self::Show { .. }
+            | Self::Render { .. }
+            | Self::List { .. }
+            | Self::Search { .. }
+            | Self::History { .. }
+            | Self::Stats { .. }
+            | Self::Export { .. }
+            | Self::Daemon
+            | Self::Notify
+            | Self::Status { .. }
+            | Self::Snooze { .. } => false,
+        }
+    }
+
+    pub fn parse(args: impl IntoIterator<Item = String>, clock: Clock) -> Self {
         // First arg is program itself
-        let mut args = args.skip(1);
+        let mut args = args.into_iter().skip(1);
 
         let Some(command) = args.next() else { help() };
         match command.as_str() {
             "add" => {
+                let mut args: Vec<_> = args.collect();
+                let flag = take_flag(&mut args, &["-p", "--priority"]);
+                let parent = take_flag(&mut args, &["--parent"])
+                    .map(|id| parse_path("add --parent", Some(id)));
+                let deadline =
+                    take_flag(&mut args, &["--due"]).map(|due| parse_deadline(&due, clock));
+                let tags = take_tags(&mut args);
+                let mut args = args.into_iter();
+
                 let Some(date) = args.next() else {
                     fatal!("no date provided to add");
                 };
-                let date = parse_date(&date);
-                let hour = match args.next() {
-                    Some(hour) => {
-                        let Ok(hour) = hour.parse() else {
-                            fatal!("failed to parse hour");
-                        };
-                        if !(0..24).contains(&hour) {
-                            fatal!("hour [{}] is not in 0..24", hour);
+                let date = parse_date(&date, clock);
+                // Subtasks go in their parent's hour
+                let hour = match parent.as_ref().map(|(hour, _)| *hour) {
+                    Some(hour) => hour,
+                    None => parse_hour("add", args.next()),
+                };
+                let (desc, priority, tags) = parse_desc("add", args.next(), flag, tags);
+                Command::Add {
+                    date,
+                    hour,
+                    desc,
+                    priority,
+                    tags,
+                    deadline,
+                    parent: parent.map(|(_, path)| path),
+                }
+            }
+            "inbox" => {
+                let mut args: Vec<_> = args.collect();
+                match args.first().map(String::as_str) {
+                    Some("add") => {
+                        args.remove(0);
+                        let flag = take_flag(&mut args, &["-p", "--priority"]);
+                        let deadline =
+                            take_flag(&mut args, &["--due"]).map(|due| parse_deadline(&due, clock));
+                        let tags = take_tags(&mut args);
+                        let mut args = args.into_iter();
+                        let (desc, priority, tags) =
+                            parse_desc("inbox add", args.next(), flag, tags);
+                        Self::InboxAdd {
+                            desc,
+                            priority,
+                            tags,
+                            deadline,
+                        }
+                    }
+                    Some("list") | None => {
+                        if !args.is_empty() {
+                            args.remove(0);
+                        }
+                        let filter = take_filter(&mut args);
+                        if let Some(arg) = args.first() {
+                            fatal!("unexpected argument [{arg}] to inbox list");
                         }
-                        hour
+                        Self::InboxList { filter }
                     }
-                    None => fatal!("no hour provided to add"),
+                    Some(arg) => fatal!("unknown inbox command [{arg}]"),
+                }
+            }
+            "schedule" => {
+                let Some(index) = args.next() else {
+                    fatal!("no inbox index provided to schedule");
+                };
+                let Ok(index) = index.parse() else {
+                    fatal!("failed to parse inbox index from [{index}]");
                 };
-                let Some(desc) = args.next() else {
-                    fatal!("no desc provided to add");
+                let Some(date) = args.next() else {
+                    fatal!("no date provided to schedule");
                 };
-                Command::Add { date, hour, desc }
+                let date = parse_date(&date, clock);
+                let hour = parse_hour("schedule", args.next());
+                Self::Schedule { index, date, hour }
             }
             "mark" => {
                 let Some(date) = args.next() else {
                     fatal!("no date provided to add");
                 };
-                let date = parse_date(&date);
-
-                let Some(id) = args.next() else {
-                    error!("no id provided to mark");
-                    fatal!("expecting format [hour.index] or [hour]");
-                };
-                match id.split_once('.') {
-                    Some((hour, index)) => {
-                        let Ok(hour) = hour.parse() else {
-                            fatal!("failed to parse hour from format [hour.index]");
-                        };
-                        let Ok(index) = index.parse() else {
-                            fatal!("failed to parse index from format [hour.index]");
-                        };
-                        if !(0..24).contains(&hour) {
-                            fatal!("hour [{}] is not in 0..24", hour);
-                        }
-                        Command::Mark { date, hour, index }
-                    }
-                    None => {
-                        let Ok(hour) = id.parse() else {
-                            fatal!("failed to parse hour from format [hour]");
-                        };
-                        if !(0..24).contains(&hour) {
-                            fatal!("hour [{}] is not in 0..24", hour);
-                        }
-                        Command::Mark {
-                            date,
-                            hour,
-                            index: 0,
-                        }
-                    }
-                }
+                let date = parse_date(&date, clock);
+
+                let (hour, path) = parse_path("mark", args.next());
+                Command::Mark { date, hour, path }
             }
             "change" => {
                 let Some(date) = args.next() else {
                     fatal!("no date provided to change");
                 };
-                let date = parse_date(&date);
-
-                let Some(id) = args.next() else {
-                    error!("no id provided to change");
-                    fatal!("expecting format [hour.index] or [hour]");
-                };
-                let (old_hour, index) = match id.split_once('.') {
-                    Some((hour, index)) => {
-                        let Ok(hour) = hour.parse() else {
-                            fatal!("failed to parse hour from format [hour.index]");
-                        };
-                        let Ok(index) = index.parse() else {
-                            fatal!("failed to parse index from format [hour.index]");
-                        };
-                        if !(0..24).contains(&hour) {
-                            fatal!("hour [{}] is not in 0..24", hour);
-                        }
-                        (hour, index)
-                    }
-                    None => {
-                        let Ok(hour) = id.parse() else {
-                            fatal!("failed to parse hour from format [hour]");
-                        };
-                        if !(0..24).contains(&hour) {
-                            fatal!("hour [{}] is not in 0..24", hour);
-                        }
-                        (hour, 0)
-                    }
-                };
+                let date = parse_date(&date, clock);
+
+                let (old_hour, index) = parse_id("change", args.next());
                 let Some(new_hour) = args.next() else {
                     fatal!("no new-hour provided to change");
                 };
@@ -184,10 +650,18 @@ impl Command {
                 }
             }
             "show" => {
+                let mut args: Vec<_> = args.collect();
+                let filter = take_filter(&mut args);
+                let verbose = take_switch(&mut args, &["--verbose", "-v"]);
+                let format = take_output(&mut args);
+
                 // if no argument provided interpret as today
-                let Some(set) = args.next() else {
+                let Some(set) = args.into_iter().next() else {
                     return Self::Show {
-                        set: ShowSet::Date(Local::now().date_naive()),
+                        set: ShowSet::Date(clock.today()),
+                        filter,
+                        verbose,
+                        format,
                     };
                 };
 
@@ -195,18 +669,27 @@ impl Command {
                     // First try to parse as number
                     Self::Show {
                         set: ShowSet::Days(days),
+                        filter,
+                        verbose,
+                        format,
                     }
                 } else {
                     // Then try to parse as date
-                    let date = parse_date(&set);
+                    let date = parse_date(&set, clock);
                     Self::Show {
                         set: ShowSet::Date(date),
+                        filter,
+                        verbose,
+                        format,
                     }
                 }
             }
             "render" => {
+                let mut args: Vec<_> = args.collect();
+                let filter = take_filter(&mut args);
+
                 // if no argument provided interpret as today
-                let Some(set) = args.next() else {
+                let Some(set) = args.into_iter().next() else {
                     fatal!("no date/count provided to render");
                 };
 
@@ -215,10 +698,270 @@ impl Command {
                     ShowSet::Days(days)
                 } else {
                     // Then try to parse as date
-                    ShowSet::Date(parse_date(&set))
+                    ShowSet::Date(parse_date(&set, clock))
+                };
+
+                Self::Render { set, filter }
+            }
+            "history" => {
+                let mut args: Vec<_> = args.collect();
+                let filter = take_filter(&mut args);
+                let format = take_output(&mut args);
+
+                // if no argument provided interpret as the last week
+                let set = match args.into_iter().next() {
+                    Some(set) => match set.parse() {
+                        Ok(days) => ShowSet::Days(days),
+                        Err(_) => ShowSet::Date(parse_date(&set, clock)),
+                    },
+                    None => ShowSet::Days(7),
+                };
+                Self::History {
+                    set,
+                    filter,
+                    format,
+                }
+            }
+            "stats" => {
+                let mut args: Vec<_> = args.collect();
+                let period = match take_flag(&mut args, &["--by"]) {
+                    Some(period) => Period::parse(&period).unwrap_or_else(|| {
+                        fatal!("failed to parse period from [{period}]");
+                    }),
+                    None => Period::default(),
+                };
+                let chart = take_flag(&mut args, &["--chart"]).map(PathBuf::from);
+                let format = take_output(&mut args);
+                let days = match args.first() {
+                    Some(days) => days.parse().unwrap_or_else(|_| {
+                        fatal!("failed to parse count from [{days}]");
+                    }),
+                    None => 30,
+                };
+                Self::Stats {
+                    days,
+                    period,
+                    chart,
+                    format,
+                }
+            }
+            "delete" => {
+                let Some(date) = args.next() else {
+                    fatal!("no date provided to delete");
+                };
+                if date == "inbox" {
+                    let Some(index) = args.next() else {
+                        fatal!("no inbox index provided to delete");
+                    };
+                    let Ok(index) = index.parse() else {
+                        fatal!("failed to parse inbox index from [{index}]");
+                    };
+                    return Self::DeleteInbox { index };
+                }
+                let date = parse_date(&date, clock);
+                let (hour, path) = parse_path("delete", args.next());
+                Self::Delete { date, hour, path }
+            }
+            "search" => {
+                let mut args: Vec<_> = args.collect();
+                let filter = take_filter(&mut args);
+                let status = take_flag(&mut args, &["--status", "-s"]).map(|status| {
+                    Status::parse(&status).unwrap_or_else(|| {
+                        fatal!("failed to parse status from [{status}]");
+                    })
+                });
+                let from = take_flag(&mut args, &["--from"]).map(|date| parse_date(&date, clock));
+                let to = take_flag(&mut args, &["--to"]).map(|date| parse_date(&date, clock));
+                let regex = take_switch(&mut args, &["--regex", "-r"]);
+                let fuzzy = take_switch(&mut args, &["--fuzzy", "-f"]);
+                let format = take_output(&mut args);
+
+                // No need to quote queries with spaces in them
+                let query = args.join(" ");
+                let pattern = match (regex, fuzzy) {
+                    (true, true) => fatal!("search can't be both --regex and --fuzzy"),
+                    (true, false) => Pattern::Regex(
+                        RegexBuilder::new(&query)
+                            .case_insensitive(true)
+                            .build()
+                            .unwrap_or_else(|e| {
+                                fatal!("invalid regex [{query}]: {e}");
+                            }),
+                    ),
+                    (false, true) => Pattern::Fuzzy(query),
+                    (false, false) => Pattern::Substring(query),
+                };
+                Self::Search {
+                    search: Search {
+                        pattern,
+                        filter,
+                        status,
+                        from,
+                        to,
+                    },
+                    format,
+                }
+            }
+            "list" => {
+                let mut args: Vec<_> = args.collect();
+                let filter = take_filter(&mut args);
+                if let Some(arg) = args.first() {
+                    fatal!("unexpected argument [{arg}] to list");
+                }
+                Self::List { filter }
+            }
+            "note" => {
+                let Some(date) = args.next() else {
+                    fatal!("no date provided to note");
+                };
+                let date = parse_date(&date, clock);
+                let (hour, path) = parse_path("note", args.next());
+                Self::Note { date, hour, path }
+            }
+            "due" => {
+                let Some(date) = args.next() else {
+                    fatal!("no date provided to due");
                 };
+                let date = parse_date(&date, clock);
+                let (hour, path) = parse_path("due", args.next());
+                let deadline = match args.next().as_deref() {
+                    Some("none") => None,
+                    Some(due) => Some(parse_deadline(due, clock)),
+                    None => fatal!("no deadline provided to due"),
+                };
+                Self::Due {
+                    date,
+                    hour,
+                    path,
+                    deadline,
+                }
+            }
+            "depend" | "undepend" => {
+                let Some(date) = args.next() else {
+                    fatal!("no date provided to {command}");
+                };
+                let date = parse_date(&date, clock);
+                let (hour, path) = parse_path(&command, args.next());
+                let Some(on_date) = args.next() else {
+                    fatal!("no date provided for the dependency to {command}");
+                };
+                let on_date = parse_date(&on_date, clock);
+                let (on_hour, on_path) = parse_path(&command, args.next());
+                Self::Depend {
+                    date,
+                    hour,
+                    path,
+                    on_date,
+                    on_hour,
+                    on_path,
+                    remove: command == "undepend",
+                }
+            }
+            "export" => {
+                let mut args: Vec<_> = args.collect();
+                let from = take_flag(&mut args, &["--from"]).map(|date| parse_date(&date, clock));
+                let to = take_flag(&mut args, &["--to"]).map(|date| parse_date(&date, clock));
+                let events = take_switch(&mut args, &["--events"]);
+                let mut args = args.into_iter().peekable();
+                let format = parse_format("export", args.next());
+                if events && format != FileFormat::Ics {
+                    fatal!("--events only works with ics");
+                }
+                if !format.checklist() {
+                    return Self::Export {
+                        format,
+                        path: args.next().filter(|path| path != "-").map(PathBuf::from),
+                        from,
+                        to,
+                        events,
+                    };
+                }
 
-                Self::Render { set }
+                if from.is_some() || to.is_some() {
+                    fatal!("checklists take a date or count instead of --from and --to");
+                }
+                // The set is optional, so anything that isn't one is the path
+                let set = match args.peek() {
+                    Some(set) if set.parse::<usize>().is_ok() => {
+                        ShowSet::Days(args.next().unwrap().parse().unwrap())
+                    }
+                    Some(set) if is_date(set) => {
+                        ShowSet::Date(parse_date(&args.next().unwrap(), clock))
+                    }
+                    _ => ShowSet::Date(clock.today()),
+                };
+                let (from, to) = match set {
+                    ShowSet::Date(date) => (date, date),
+                    ShowSet::Days(days) => (
+                        clock.today(),
+                        clock.today() + chrono::Days::new(days.saturating_sub(1) as u64),
+                    ),
+                };
+                Self::Export {
+                    format,
+                    path: args.next().filter(|path| path != "-").map(PathBuf::from),
+                    from: Some(from),
+                    to: Some(to),
+                    events,
+                }
+            }
+            "import" => {
+                let mut args: Vec<_> = args.collect();
+                let date = take_flag(&mut args, &["--date"]).map(|date| parse_date(&date, clock));
+                let mut args = args.into_iter();
+                let format = parse_format("import", args.next());
+                if date.is_some() && !format.checklist() {
+                    fatal!("--date only works with md and org");
+                }
+                let Some(path) = args.next() else {
+                    fatal!("no path provided to import");
+                };
+                Self::Import {
+                    format,
+                    path: PathBuf::from(path),
+                    date,
+                }
+            }
+            "daemon" => Self::Daemon,
+            "notify" => Self::Notify,
+            "status" => {
+                let mut args: Vec<_> = args.collect();
+                let watch = take_switch(&mut args, &["--watch", "-w"]);
+                let bar = match args.first() {
+                    Some(bar) => Bar::parse(bar).unwrap_or_else(|| {
+                        fatal!("unknown bar [{bar}]");
+                    }),
+                    None => Bar::default(),
+                };
+                Self::Status { bar, watch }
+            }
+            "tui" => Self::Tui,
+            "batch" => Self::Batch {
+                path: PathBuf::from(args.next().unwrap_or("-".to_string())),
+            },
+            "repl" => Self::Repl,
+            "snooze" | "remind" => {
+                let Some(date) = args.next() else {
+                    fatal!("no date provided to {command}");
+                };
+                let date = parse_date(&date, clock);
+                let (hour, index) = parse_id(&command, args.next());
+                let minutes = parse_minutes(&command, args.next());
+                if command == "snooze" {
+                    Self::Snooze {
+                        date,
+                        hour,
+                        index,
+                        minutes,
+                    }
+                } else {
+                    Self::Remind {
+                        date,
+                        hour,
+                        index,
+                        minutes,
+                    }
+                }
             }
             _ => help(),
         }
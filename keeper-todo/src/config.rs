@@ -0,0 +1,136 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use anyhow::{anyhow, Context};
+use image::Rgb;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    filter::{Expr, Filter},
+    wallpaper::{Backend, Monitor},
+};
+
+const CONFIG_PATH: &str = concat!(env!("HOME"), "/.local/share/keeper/config.ron");
+
+/// User settings, read from `config.ron` next to the data file. Every field has
+/// a default, so the file (and any field in it) is optional.
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct Config {
+    pub font: FontConfig,
+    pub wallpaper: WallpaperConfig,
+    pub reminders: ReminderConfig,
+}
+
+/// Fonts are given either as a path to a font file or as a fontconfig family
+/// name (e.g. `"Noto Sans CJK JP"`), which is resolved with `fc-match`.
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct FontConfig {
+    /// Used for task descriptions. Defaults to the embedded Iosevka.
+    pub regular: Option<String>,
+    /// Used for date headers. Defaults to the regular font, drawn thicker.
+    pub bold: Option<String>,
+    /// Used for completed tasks. Defaults to the regular font, slanted.
+    pub italic: Option<String>,
+    /// Tried in order for any glyph the fonts above are missing.
+    pub fallback: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct WallpaperConfig {
+    pub backend: Backend,
+    /// One wallpaper is rendered per monitor. If empty, monitors are detected
+    /// with `xrandr`/`wlr-randr`.
+    pub monitors: Vec<Monitor>,
+    /// Tag expressions limiting what the wallpaper shows during some hours,
+    /// e.g. only `+work` from 9 to 17. The first one covering the current hour
+    /// is used.
+    pub filters: Vec<ScheduledFilter>,
+    /// Colors for tags on the wallpaper, e.g. `"+work": "#88c0d0"`.
+    pub tag_colors: BTreeMap<String, String>,
+    /// Whether to list unfinished inbox tasks below the schedule.
+    pub inbox: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ScheduledFilter {
+    /// First hour the filter applies to.
+    pub from: usize,
+    /// Hour the filter stops applying, exclusive.
+    pub to: usize,
+    pub tags: String,
+}
+
+impl WallpaperConfig {
+    /// The filter to render with at `hour`, if any.
+    pub fn filter_at(&self, hour: usize) -> anyhow::Result<Filter> {
+        let Some(scheduled) = self
+            .filters
+            .iter()
+            .find(|filter| (filter.from..filter.to).contains(&hour))
+        else {
+            return Ok(Filter::default());
+        };
+        let tags = Expr::parse(&scheduled.tags)
+            .with_context(|| format!("invalid wallpaper filter '{}'", scheduled.tags))?;
+        Ok(Filter {
+            tags: Some(tags),
+            ..Filter::default()
+        })
+    }
+
+    pub fn tag_colors(&self) -> anyhow::Result<BTreeMap<String, Rgb<u8>>> {
+        self.tag_colors
+            .iter()
+            .map(|(tag, color)| {
+                let rgb = parse_color(color)
+                    .ok_or_else(|| anyhow!("invalid color '{color}' for tag {tag}"))?;
+                Ok((tag.to_lowercase(), rgb))
+            })
+            .collect()
+    }
+}
+
+/// Parses a `#rrggbb` hex color.
+fn parse_color(color: &str) -> Option<Rgb<u8>> {
+    let hex = color.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some(Rgb([channel(0)?, channel(2)?, channel(4)?]))
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default)]
+pub struct ReminderConfig {
+    /// How many minutes before a task's hour to remind about it, unless the
+    /// task has its own lead time.
+    pub lead_minutes: u32,
+    /// Whether to also send a reminder when a task's hour passes and it still
+    /// isn't complete.
+    pub overdue: bool,
+}
+
+impl Default for ReminderConfig {
+    fn default() -> Self {
+        Self {
+            lead_minutes: 10,
+            overdue: true,
+        }
+    }
+}
+
+impl Config {
+    pub fn load() -> anyhow::Result<Self> {
+        let path = Path::new(CONFIG_PATH);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("failed to read config from {CONFIG_PATH}"))?;
+        ron::from_str(&contents).with_context(|| format!("failed to parse config at {CONFIG_PATH}"))
+    }
+}
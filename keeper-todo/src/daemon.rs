@@ -0,0 +1,109 @@
+use std::{
+    sync::mpsc::{self, RecvTimeoutError},
+    time::Duration,
+};
+
+use anyhow::{bail, Context};
+use chrono::{DateTime, Local, TimeZone, Timelike};
+use keeper_util::{clock::Clock, error, info, DataManager};
+use notify::{RecursiveMode, Watcher};
+
+use crate::{cli::ShowSet, config::Config, data::Keeper, filter::Filter, reminder::ReminderState};
+
+/// How long the data file has to be left alone before we render. A single
+/// command or editor save can touch it several times in quick succession.
+pub(crate) const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Keeps the wallpaper up to date: re-renders every minute, so that the time
+/// next to the now marker stays right and slots go overdue on time, and
+/// whenever the data file changes. Also sends reminders as they come due.
+/// Never returns unless the watcher dies.
+pub fn run(dm: &DataManager<Keeper>, clock: Clock) -> anyhow::Result<()> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).context("failed to create file watcher")?;
+    // Watch the directory rather than the file itself, since editors (and git)
+    // like to replace files instead of writing to them
+    watcher
+        .watch(dm.data_dir(), RecursiveMode::NonRecursive)
+        .with_context(|| format!("failed to watch {:?}", dm.data_dir()))?;
+
+    let mut next_reminder = refresh(dm, clock);
+    loop {
+        let now = clock.now();
+        let next = match next_reminder {
+            Some(reminder) => reminder.min(next_minute(now)),
+            None => next_minute(now),
+        };
+        let timeout = (next - now).to_std().unwrap_or_default();
+        match rx.recv_timeout(timeout) {
+            Ok(Ok(event)) => {
+                if !event
+                    .paths
+                    .iter()
+                    .any(|path| path.ends_with(dm.data_filename()))
+                {
+                    continue;
+                }
+                // Coalesce bursts of changes into a single render
+                while rx.recv_timeout(DEBOUNCE).is_ok() {}
+                next_reminder = refresh(dm, clock);
+            }
+            Ok(Err(e)) => error!("file watcher error: {e}"),
+            Err(RecvTimeoutError::Timeout) => next_reminder = refresh(dm, clock),
+            Err(RecvTimeoutError::Disconnected) => bail!("file watcher stopped"),
+        }
+    }
+}
+
+/// Reloads the keeper from disk, renders it and sends any due reminders.
+/// Returns when the next reminder is due. Errors are reported rather than
+/// returned so that one bad edit doesn't take down the daemon.
+fn refresh(dm: &DataManager<Keeper>, clock: Clock) -> Option<DateTime<Local>> {
+    let mut keeper = match dm.load_data() {
+        Ok(keeper) => keeper,
+        Err(e) => {
+            error!("failed to load data: {e:#}");
+            return None;
+        }
+    };
+
+    keeper.set_clock(clock);
+    let now = clock.now();
+    match keeper.render(ShowSet::Date(now.date_naive()), &Filter::default()) {
+        Ok(()) => info!("rendered wallpaper at {}", now.format("%H:%M:%S")),
+        Err(e) => error!("failed to render wallpaper: {e:#}"),
+    }
+
+    let result = Config::load().and_then(|config| {
+        let mut state = ReminderState::load(dm.data_dir())?;
+        state.notify(&keeper, &config.reminders, now);
+        state.save()?;
+        Ok(state.next(&keeper, &config.reminders, now))
+    });
+    match result {
+        Ok(next) => next,
+        Err(e) => {
+            error!("failed to send reminders: {e:#}");
+            None
+        }
+    }
+}
+
+/// The wallpaper always shows today, and with it the current time on the now
+/// marker, which changes every minute.
+pub fn next_minute(now: DateTime<Local>) -> DateTime<Local> {
+    let minute = now.with_second(0).and_then(|now| now.with_nanosecond(0));
+    minute.unwrap_or(now) + chrono::Duration::minutes(1)
+}
+
+/// Slots go overdue on the hour (and the day changes at midnight), so that's
+/// when anything without the time on it needs to change.
+pub fn next_refresh(now: DateTime<Local>) -> DateTime<Local> {
+    let next_hour =
+        now.date_naive().and_hms_opt(now.hour(), 0, 0).unwrap() + chrono::Duration::hours(1);
+    Local
+        .from_local_datetime(&next_hour)
+        .earliest()
+        // The next hour was skipped by a DST transition
+        .unwrap_or(now + chrono::Duration::hours(1))
+}
@@ -1,50 +1,484 @@
-use std::{collections::BTreeMap, fmt::Display, path::Path, process};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::Display,
+    path::PathBuf,
+    process,
+};
 
 use anyhow::Context;
-use chrono::{Local, NaiveDate, TimeZone};
-use image::{ImageBuffer, Rgb};
-use imageproc::drawing::{draw_text_mut, text_size};
-use rusttype::{Font, Scale};
+use chrono::{
+    DateTime, Days, Duration, Local, LocalResult, NaiveDate, NaiveDateTime, TimeZone, Timelike,
+};
 use serde::{Deserialize, Serialize};
 
-use crate::cli::ShowSet;
+use crate::{
+    cli::ShowSet,
+    config::Config,
+    details::{ChecklistItem, Details},
+    filter::Filter,
+    font::Fonts,
+    render::{KeeperRenderer, NORD_BG},
+    search::{Hit, Search},
+    wallpaper::Monitor,
+};
 use keeper_util::{
-    color::{BLUE, GREEN, RED, RESET},
-    fatal, info,
+    clock::Clock,
+    color::{BLUE, CYAN, GREEN, PURPLE, RED, RESET, YELLOW},
+    fatal, info, warn,
 };
 
 const HOME: &str = env!("HOME");
 // Not on the $PATH that cron uses, so we hardcode it here
 const FD: &str = concat!(env!("HOME"), "/.cargo/bin/fd");
+/// How far ahead `show` and the wallpaper look for deadlines.
+const UPCOMING_DAYS: u64 = 7;
+/// Where imported tasks that only have a date go, the start of a working day.
+pub const ALL_DAY_HOUR: usize = 9;
+
+/// How important a task is. Variants are ordered most important first, so
+/// sorting by priority puts urgent tasks at the front.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    Urgent,
+    High,
+    #[default]
+    Normal,
+    Low,
+}
+
+impl Priority {
+    /// Parses a priority name (`urgent`, `high`, `normal`, `low`) or level
+    /// (`1` to `4`, 1 being the most important).
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "urgent" | "1" => Some(Self::Urgent),
+            "high" | "2" => Some(Self::High),
+            "normal" | "3" => Some(Self::Normal),
+            "low" | "4" => Some(Self::Low),
+            _ => None,
+        }
+    }
 
-const SCREEN_HEIGHT: u32 = 956;
-const SCREEN_WIDTH: u32 = 1470;
-const Y_START: u32 = 35;
-const Y_PAD: u32 = 20;
-const X_PAD: u32 = 35;
-const CHAR_HEIGHT_TO_WIDTH: f32 = 1.9;
-const NORD_BG: Rgb<u8> = Rgb([0x2e, 0x34, 0x40]);
-const NORD_GREEN: Rgb<u8> = Rgb([0xa3, 0xbe, 0x8c]);
-const NORD_RED: Rgb<u8> = Rgb([0xbf, 0x61, 0x6a]);
-const NORD_WHITE: Rgb<u8> = Rgb([0xd8, 0xde, 0xe9]);
-const NORD_BLUE: Rgb<u8> = Rgb([0x81, 0xa1, 0xc1]);
+    /// Shown in front of a task's description. Normal tasks don't get one, so
+    /// the others stand out.
+    pub fn marker(&self) -> &'static str {
+        match self {
+            Self::Urgent => "!!",
+            Self::High => "!",
+            Self::Normal => "",
+            Self::Low => "~",
+        }
+    }
+}
+
+/// When a task has to be done by, as opposed to when it's scheduled.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Deadline {
+    pub date: NaiveDate,
+    /// Due by the end of this hour, or the end of the day if there's none.
+    pub hour: Option<usize>,
+}
+
+impl Deadline {
+    pub fn hour(&self) -> usize {
+        self.hour.unwrap_or(23)
+    }
+
+    pub fn passed(&self, now: DateTime<Local>) -> bool {
+        past_due(self.date, self.hour(), now)
+    }
+
+    /// How long until the deadline, like `today` or `in 3 days`.
+    pub fn countdown(&self, now: DateTime<Local>) -> String {
+        if self.passed(now) {
+            return "overdue".to_string();
+        }
+        match (self.date - now.date_naive()).num_days() {
+            0 => "today".to_string(),
+            1 => "tomorrow".to_string(),
+            days => format!("in {days} days"),
+        }
+    }
+
+    /// Whether a task scheduled at `hour` on `date` would miss the deadline.
+    pub fn missed_by(&self, date: NaiveDate, hour: usize) -> bool {
+        (date, hour) > (self.date, self.hour())
+    }
+}
+
+impl Display for Deadline {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.date.format("%d %b"))?;
+        if let Some(hour) = self.hour {
+            write!(f, " {hour}:00")?;
+        }
+        Ok(())
+    }
+}
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Task {
+    /// Stays the same when the task moves around, unlike its hour and index.
+    /// 0 until one is assigned.
+    #[serde(default)]
+    id: u64,
     completed: bool,
     desc: String,
+    #[serde(default)]
+    priority: Priority,
+    /// `+project` and `@context` tags, including the sigil.
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    details: Details,
+    #[serde(default)]
+    subtasks: Vec<Task>,
+    /// Ids of the tasks that have to be done before this one.
+    #[serde(default)]
+    depends: Vec<u64>,
+    /// Minutes before the task's hour to send a reminder, overriding the
+    /// configured default.
+    #[serde(default)]
+    remind: Option<u32>,
+    #[serde(default)]
+    deadline: Option<Deadline>,
+    /// When the task was added. Only missing for data from before these were
+    /// recorded, until [`Keeper::backfill_timestamps`] runs.
+    #[serde(default)]
+    created: Option<DateTime<Local>>,
+    /// When the task last changed.
+    #[serde(default)]
+    modified: Option<DateTime<Local>>,
+    /// When the task was marked complete.
+    #[serde(default)]
+    completed_at: Option<DateTime<Local>>,
+    /// How many times the task has been pushed back to a later hour.
+    #[serde(default)]
+    deferred: u32,
+    /// The UID of the task in another calendar, for tasks that came from one.
+    #[serde(default)]
+    uid: Option<String>,
 }
 
 impl Task {
     pub fn new(desc: String) -> Self {
         Self {
+            id: 0,
             completed: false,
             desc,
+            priority: Priority::default(),
+            tags: Vec::new(),
+            details: Details::default(),
+            subtasks: Vec::new(),
+            depends: Vec::new(),
+            remind: None,
+            deadline: None,
+            created: None,
+            modified: None,
+            completed_at: None,
+            deferred: 0,
+            uid: None,
+        }
+    }
+
+    pub fn with_priority(desc: String, priority: Priority) -> Self {
+        Self {
+            priority,
+            ..Self::new(desc)
+        }
+    }
+
+    /// Marks the task complete at `now`, along with all of its subtasks.
+    /// Subtasks that were already done keep their completion time.
+    pub fn mark_complete(&mut self, now: DateTime<Local>) {
+        if !self.completed {
+            self.completed = true;
+            self.completed_at = Some(now);
+            self.touch(now);
+        }
+        for subtask in &mut self.subtasks {
+            subtask.mark_complete(now);
+        }
+    }
+
+    /// Sets when this task was done, or that it isn't, without touching its
+    /// subtasks. For importing tasks as they were, rather than marking them.
+    pub fn set_completed_at(&mut self, at: Option<DateTime<Local>>) {
+        self.completed = at.is_some();
+        self.completed_at = at;
+    }
+
+    /// Marks the subtask at `path` (or this task, if `path` is empty) complete.
+    /// Parents whose subtasks are now all complete are marked complete too.
+    /// Returns the description of the marked task, if there is one at `path`.
+    pub fn mark_path(&mut self, path: &[usize], now: DateTime<Local>) -> Option<String> {
+        let Some((index, rest)) = path.split_first() else {
+            self.mark_complete(now);
+            return Some(self.desc.clone());
+        };
+        let desc = self.subtasks.get_mut(*index)?.mark_path(rest, now)?;
+        if self.subtasks.iter().all(|subtask| subtask.completed) {
+            self.mark_complete(now);
+        }
+        Some(desc)
+    }
+
+    /// Undoes [`Task::mark_path`] for the subtask at `path` (or this task), and
+    /// every task above it, since they aren't all done anymore. Returns the
+    /// description of the unmarked task, if there is one at `path`.
+    pub fn unmark_path(&mut self, path: &[usize], now: DateTime<Local>) -> Option<String> {
+        let desc = match path.split_first() {
+            Some((index, rest)) => self.subtasks.get_mut(*index)?.unmark_path(rest, now)?,
+            None => self.desc.clone(),
+        };
+        if self.completed {
+            self.completed = false;
+            self.completed_at = None;
+            self.touch(now);
+        }
+        Some(desc)
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn completed(&self) -> bool {
+        self.completed
+    }
+
+    pub fn desc(&self) -> &str {
+        &self.desc
+    }
+
+    pub fn set_desc(&mut self, desc: String) {
+        self.desc = desc;
+    }
+
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
+
+    pub fn set_priority(&mut self, priority: Priority) {
+        self.priority = priority;
+    }
+
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    pub fn add_tag(&mut self, tag: &str) {
+        if !self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            self.tags.push(tag.to_string());
+        }
+    }
+
+    pub fn details(&self) -> &Details {
+        &self.details
+    }
+
+    pub fn set_details(&mut self, details: Details) {
+        self.details = details;
+    }
+
+    pub fn subtasks(&self) -> &[Task] {
+        &self.subtasks
+    }
+
+    pub fn add_subtask(&mut self, subtask: Task) {
+        // A finished task gets new work to do
+        self.completed = false;
+        self.completed_at = None;
+        self.subtasks.push(subtask);
+    }
+
+    /// The subtask at `path`, or this task if `path` is empty.
+    pub fn subtask(&self, path: &[usize]) -> Option<&Task> {
+        match path.split_first() {
+            Some((index, rest)) => self.subtasks.get(*index)?.subtask(rest),
+            None => Some(self),
+        }
+    }
+
+    pub fn subtask_mut(&mut self, path: &[usize]) -> Option<&mut Task> {
+        match path.split_first() {
+            Some((index, rest)) => self.subtasks.get_mut(*index)?.subtask_mut(rest),
+            None => Some(self),
+        }
+    }
+
+    /// How many subtasks (at any depth) are complete, out of how many.
+    pub fn subtask_progress(&self) -> Option<(usize, usize)> {
+        if self.subtasks.is_empty() {
+            return None;
+        }
+        let (mut done, mut total) = (0, 0);
+        for subtask in &self.subtasks {
+            done += subtask.completed as usize;
+            total += 1;
+            if let Some((d, t)) = subtask.subtask_progress() {
+                done += d;
+                total += t;
+            }
+        }
+        Some((done, total))
+    }
+
+    fn order(&mut self) {
+        sort(&mut self.subtasks);
+    }
+
+    pub fn depends(&self) -> &[u64] {
+        &self.depends
+    }
+
+    pub fn add_dependency(&mut self, id: u64) {
+        if !self.depends.contains(&id) {
+            self.depends.push(id);
+        }
+    }
+
+    /// This task and all of its subtasks, at any depth.
+    fn walk<'a>(&'a self, tasks: &mut Vec<&'a Task>) {
+        tasks.push(self);
+        for subtask in &self.subtasks {
+            subtask.walk(tasks);
+        }
+    }
+
+    fn walk_mut(&mut self, f: &mut impl FnMut(&mut Task)) {
+        f(self);
+        for subtask in &mut self.subtasks {
+            subtask.walk_mut(f);
+        }
+    }
+
+    pub fn remind(&self) -> Option<u32> {
+        self.remind
+    }
+
+    pub fn deadline(&self) -> Option<Deadline> {
+        self.deadline
+    }
+
+    pub fn set_deadline(&mut self, deadline: Option<Deadline>) {
+        self.deadline = deadline;
+    }
+
+    pub fn created(&self) -> Option<DateTime<Local>> {
+        self.created
+    }
+
+    pub fn modified(&self) -> Option<DateTime<Local>> {
+        self.modified
+    }
+
+    pub fn completed_at(&self) -> Option<DateTime<Local>> {
+        self.completed_at
+    }
+
+    pub fn deferred(&self) -> u32 {
+        self.deferred
+    }
+
+    /// Counts the task as put off until later.
+    pub fn defer(&mut self) {
+        self.deferred += 1;
+    }
+
+    /// Identifies the task to other programs. Tasks that didn't come from
+    /// somewhere else get one made up from their id and creation time.
+    pub fn uid(&self) -> String {
+        match &self.uid {
+            Some(uid) => uid.clone(),
+            None => format!(
+                "{}-{}@keeper",
+                self.id,
+                self.created.map_or(0, |created| created.timestamp())
+            ),
+        }
+    }
+
+    pub fn set_uid(&mut self, uid: String) {
+        self.uid = Some(uid);
+    }
+
+    /// Takes everything another program knows about the task from `other`,
+    /// keeping what only keeper knows, like its id, subtasks and dependencies.
+    /// Notes are kept too if `other` has none, since most formats can't
+    /// store them. Subtasks are matched up by description.
+    fn update_from(&mut self, other: Task, now: DateTime<Local>) {
+        let details = match other.details.is_empty() {
+            true => self.details.clone(),
+            false => other.details,
+        };
+        // Keeper knows better when it was done if it already was
+        let completed_at = match self.completed && other.completed {
+            true => self.completed_at,
+            false => other.completed_at,
+        };
+        let mut changed = self.desc != other.desc
+            || self.priority != other.priority
+            || self.tags != other.tags
+            || self.details != details
+            || self.deadline != other.deadline
+            || self.completed != other.completed;
+
+        self.desc = other.desc;
+        self.priority = other.priority;
+        self.tags = other.tags;
+        self.details = details;
+        self.deadline = other.deadline;
+        self.completed = other.completed;
+        self.completed_at = completed_at;
+        for subtask in other.subtasks {
+            match self.subtasks.iter_mut().find(|s| s.desc == subtask.desc) {
+                Some(existing) => existing.update_from(subtask, now),
+                None => {
+                    self.subtasks.push(subtask);
+                    changed = true;
+                }
+            }
+        }
+        if changed {
+            self.modified = other.modified.or(Some(now));
+        }
+    }
+
+    /// Records that the task changed at `now`. New tasks are created then too.
+    pub fn touch(&mut self, now: DateTime<Local>) {
+        self.created.get_or_insert(now);
+        self.modified = Some(now);
+    }
+
+    /// Whether the task is late: past its deadline if it has one, otherwise
+    /// whether its slot has passed.
+    pub fn overdue(&self, slot_passed: bool, now: DateTime<Local>) -> bool {
+        self.deadline
+            .map_or(slot_passed, |deadline| deadline.passed(now))
+    }
+}
+
+/// Warns about `task` or any of its subtasks being scheduled at `hour` on
+/// `date`, after their deadline.
+fn warn_if_late(date: NaiveDate, hour: usize, task: &Task) {
+    let mut tasks = Vec::new();
+    task.walk(&mut tasks);
+    for task in tasks.into_iter().filter(|task| !task.completed) {
+        if let Some(deadline) = task.deadline.filter(|d| d.missed_by(date, hour)) {
+            warn!(
+                "'{}' is scheduled after its deadline, {deadline}",
+                task.desc
+            );
         }
     }
+}
 
-    pub fn mark_complete(&mut self) {
-        self.completed = true;
+/// Unfinished tasks first, most important first.
+fn sort(tasks: &mut [Task]) {
+    tasks.sort_by_key(|task| (task.completed, task.priority));
+    for task in tasks {
+        task.order();
     }
 }
 
@@ -56,27 +490,308 @@ pub struct Schedule {
 #[derive(Serialize, Deserialize, Debug, Default)]
 pub struct Keeper {
     pub days: BTreeMap<NaiveDate, Schedule>,
+    /// Tasks that haven't been given a date and hour yet.
+    #[serde(default)]
+    pub inbox: Vec<Task>,
+    /// The id the next task will get.
+    #[serde(default)]
+    next_id: u64,
+    #[serde(skip)]
+    clock: Clock,
+    #[serde(skip)]
+    defer_render: bool,
+}
+
+/// A task read from another program's file, see [`Keeper::import`].
+pub struct ImportedTask {
+    pub task: Task,
+    /// Where the task is planned, or `None` for the inbox.
+    pub slot: Option<(NaiveDate, usize)>,
+    /// The uid of the task this is a subtask of.
+    pub parent: Option<String>,
+}
+
+/// Where a task is: its date and hour or the inbox, then the path to it.
+enum Place {
+    Slot(NaiveDate, usize, Vec<usize>),
+    Inbox(Vec<usize>),
+}
+
+/// Whether the hour slot `hour` on `date` has passed. For example, if hour =
+/// 10, then we are at 11:00 o'clock or later.
+///
+/// If the end of the slot happens twice (clocks going back), the slot isn't
+/// over until the second time around. If it never happens (clocks going
+/// forward), the slot is over as soon as the clocks jump.
+pub fn past_due(date: NaiveDate, hour: usize, now: DateTime<Local>) -> bool {
+    let last_second = date.and_hms_opt(0, 59, 59).unwrap() + Duration::hours(hour as i64);
+    // Not `.latest()`, some versions of chrono don't order the two candidates
+    // by time
+    let cutoff = match Local.from_local_datetime(&last_second) {
+        LocalResult::Single(cutoff) => cutoff,
+        LocalResult::Ambiguous(a, b) => a.max(b),
+        LocalResult::None => {
+            let next_hour = last_second + Duration::seconds(1);
+            match Local.from_local_datetime(&next_hour).earliest() {
+                Some(next_hour) => next_hour - Duration::seconds(1),
+                // A gap of over an hour, which no real timezone has
+                None => return false,
+            }
+        }
+    };
+    cutoff < now
 }
 
 impl Keeper {
+    pub fn clock(&self) -> Clock {
+        self.clock
+    }
+
+    pub fn set_clock(&mut self, clock: Clock) {
+        self.clock = clock;
+    }
+
+    /// Stops commands from rendering the wallpaper each time, for running a
+    /// lot of them and rendering once at the end.
+    pub fn defer_render(&mut self, defer: bool) {
+        self.defer_render = defer;
+    }
+
+    /// Gives every task that doesn't have an id yet (new tasks, and tasks from
+    /// before there were ids) one. Returns the ids it handed out.
+    pub fn assign_ids(&mut self) -> Vec<u64> {
+        let mut assigned = Vec::new();
+        let next_id = &mut self.next_id;
+        let scheduled = self
+            .days
+            .values_mut()
+            .flat_map(|schedule| schedule.timeslots.values_mut().flatten());
+        for task in scheduled.chain(self.inbox.iter_mut()) {
+            task.walk_mut(&mut |task| {
+                if task.id == 0 {
+                    *next_id = (*next_id).max(1);
+                    task.id = *next_id;
+                    assigned.push(task.id);
+                    *next_id += 1;
+                }
+            });
+        }
+        assigned
+    }
+
+    /// Fills in timestamps for tasks from before they were recorded. Those are
+    /// taken to have been created (and last changed) at the start of their
+    /// day, and completed by the end of their hour, but never later than now.
+    /// Undated tasks in the inbox are taken to be new.
+    pub fn backfill_timestamps(&mut self) {
+        let now = self.clock.now();
+        let local = |time: Option<NaiveDateTime>| {
+            time.and_then(|time| Local.from_local_datetime(&time).earliest())
+                .map_or(now, |time| time.min(now))
+        };
+        let backfill = |task: &mut Task, created, completed| {
+            task.created.get_or_insert(created);
+            task.modified.get_or_insert(created);
+            if task.completed {
+                task.completed_at.get_or_insert(completed);
+            }
+        };
+        for (date, schedule) in &mut self.days {
+            for (hour, tasks) in &mut schedule.timeslots {
+                let created = local(date.and_hms_opt(0, 0, 0));
+                let completed = local(date.and_hms_opt(*hour as u32, 59, 59));
+                for task in tasks {
+                    task.walk_mut(&mut |task| backfill(task, created, completed));
+                }
+            }
+        }
+        for task in &mut self.inbox {
+            task.walk_mut(&mut |task| backfill(task, now, now));
+        }
+    }
+
+    /// Every task, including subtasks and the inbox, in order.
+    pub fn tasks(&self) -> Vec<&Task> {
+        let mut tasks = Vec::new();
+        for schedule in self.days.values() {
+            for task in schedule.timeslots.values().flatten() {
+                task.walk(&mut tasks);
+            }
+        }
+        for task in &self.inbox {
+            task.walk(&mut tasks);
+        }
+        tasks
+    }
+
+    pub fn find(&self, id: u64) -> Option<&Task> {
+        self.tasks().into_iter().find(|task| task.id == id)
+    }
+
+    /// Every task with an id, by id. Anything looking up dependencies for many
+    /// tasks should build this once and use it instead of [`Keeper::find`].
+    pub fn index(&self) -> TaskIndex<'_> {
+        TaskIndex(
+            self.tasks()
+                .into_iter()
+                .filter(|task| task.id != 0)
+                .map(|task| (task.id, task))
+                .collect(),
+        )
+    }
+
+    /// [`TaskIndex::blockers`] for a single task.
+    pub fn blockers(&self, task: &Task) -> Vec<&Task> {
+        self.index().blockers(task)
+    }
+
+    /// [`TaskIndex::blocked`] for a single task.
+    pub fn blocked(&self, task: &Task) -> bool {
+        self.index().blocked(task)
+    }
+
+    /// Whether `from` depends on `to`, directly or through other tasks.
+    pub fn depends_on(&self, from: u64, to: u64) -> bool {
+        let by_id = self.index();
+        let mut seen = BTreeSet::new();
+        let mut stack = vec![from];
+        while let Some(id) = stack.pop() {
+            if id == to {
+                return true;
+            }
+            if !seen.insert(id) {
+                continue;
+            }
+            if let Some(task) = by_id.find(id) {
+                stack.extend(&task.depends);
+            }
+        }
+        false
+    }
+
+    /// Makes the task at `path` depend on the one at `on` (both `(date, hour,
+    /// path)`), or stop depending on it if `remove` is set.
+    pub fn depend(
+        &mut self,
+        (date, hour, path): (NaiveDate, usize, &[usize]),
+        (on_date, on_hour, on_path): (NaiveDate, usize, &[usize]),
+        remove: bool,
+    ) -> anyhow::Result<()> {
+        self.assign_ids();
+        let Some(dependency) = self.subtask(on_date, on_hour, on_path) else {
+            fatal!("no task at {} on {on_date}", fmt_id(on_hour, on_path));
+        };
+        let (dependency_id, dependency_desc) = (dependency.id, dependency.desc.clone());
+        let Some(task) = self.subtask(date, hour, path) else {
+            fatal!("no task at {} on {date}", fmt_id(hour, path));
+        };
+        if dependency_id == task.id {
+            fatal!("a task can't depend on itself");
+        }
+        if !remove && self.depends_on(dependency_id, task.id) {
+            fatal!(
+                "'{dependency_desc}' already depends on '{}', that would be a cycle",
+                task.desc
+            );
+        }
+
+        let now = self.clock.now();
+        let task = self
+            .subtask_mut(date, hour, path)
+            .expect("task was found above");
+        task.touch(now);
+        if remove {
+            task.depends.retain(|id| *id != dependency_id);
+            info!("'{}' no longer depends on '{dependency_desc}'", task.desc);
+        } else if task.depends.contains(&dependency_id) {
+            info!("'{}' already depends on '{dependency_desc}'", task.desc);
+            return Ok(());
+        } else {
+            task.add_dependency(dependency_id);
+            info!("'{}' now depends on '{dependency_desc}'", task.desc);
+        }
+
+        self.render(ShowSet::Date(self.clock.today()), &Filter::default())
+    }
+
     pub fn order(&mut self) {
         for schedule in self.days.values_mut() {
             for slot in schedule.timeslots.values_mut() {
-                slot.sort_by_key(|task| task.completed)
+                sort(slot);
             }
         }
+        sort(&mut self.inbox);
     }
 
-    pub fn add(&mut self, date: NaiveDate, desc: &str, hour: usize) -> anyhow::Result<()> {
+    pub fn add(&mut self, date: NaiveDate, hour: usize, mut task: Task) -> anyhow::Result<()> {
+        task.touch(self.clock.now());
+        warn_if_late(date, hour, &task);
         self.days
             .entry(date)
             .or_default()
             .timeslots
             .entry(hour)
             .or_default()
-            .push(Task::new(desc.to_string()));
+            .push(task);
+
+        self.assign_ids();
+        self.order();
+        self.render(ShowSet::Date(self.clock.today()), &Filter::default())
+    }
 
-        self.render(ShowSet::Date(Local::now().date_naive()))
+    /// Adds `task` to the inbox, to be scheduled later.
+    pub fn inbox_add(&mut self, mut task: Task) -> anyhow::Result<()> {
+        task.touch(self.clock.now());
+        info!("added '{}' to the inbox", task.desc);
+        self.inbox.push(task);
+
+        self.assign_ids();
+        self.order();
+        self.render(ShowSet::Date(self.clock.today()), &Filter::default())
+    }
+
+    /// Moves the task at `index` in the inbox to `hour` on `date`.
+    pub fn schedule(&mut self, index: usize, date: NaiveDate, hour: usize) -> anyhow::Result<()> {
+        if index >= self.inbox.len() {
+            fatal!("no task at index {index} in the inbox");
+        }
+        let mut task = self.inbox.remove(index);
+        task.touch(self.clock.now());
+        info!("scheduled '{}' for {hour} on {date}", task.desc);
+        warn_if_late(date, hour, &task);
+        self.days
+            .entry(date)
+            .or_default()
+            .timeslots
+            .entry(hour)
+            .or_default()
+            .push(task);
+
+        self.order();
+        self.render(ShowSet::Date(self.clock.today()), &Filter::default())
+    }
+
+    /// Adds `task` under the task (or subtask) at `path`.
+    pub fn add_subtask(
+        &mut self,
+        date: NaiveDate,
+        hour: usize,
+        path: &[usize],
+        mut task: Task,
+    ) -> anyhow::Result<()> {
+        let now = self.clock.now();
+        let Some(parent) = self.subtask_mut(date, hour, path) else {
+            fatal!("no task at {}", fmt_id(hour, path));
+        };
+        info!("added '{}' under '{}'", task.desc, parent.desc);
+        task.touch(now);
+        parent.touch(now);
+        warn_if_late(date, hour, &task);
+        parent.add_subtask(task);
+
+        self.assign_ids();
+        self.order();
+        self.render(ShowSet::Date(self.clock.today()), &Filter::default())
     }
 
     pub fn change(
@@ -86,16 +801,21 @@ impl Keeper {
         index: usize,
         new_hour: usize,
     ) -> anyhow::Result<()> {
+        let now = self.clock.now();
         let Some(day) = self.days.get_mut(&date) else {
             fatal!("no tasks today");
         };
         let Some(tasks) = day.timeslots.get_mut(&old_hour) else {
             fatal!("no task at hour [{old_hour}]");
         };
-        if index > tasks.len() {
+        if index >= tasks.len() {
             fatal!("index {index} is too large for hour {old_hour}");
         }
-        let task = tasks.remove(index);
+        let mut task = tasks.remove(index);
+        task.touch(now);
+        if new_hour > old_hour {
+            task.defer();
+        }
 
         // delete old_hour tasks vec if empty
         if tasks.is_empty() {
@@ -103,32 +823,393 @@ impl Keeper {
         }
 
         info!("moved '{}' from {old_hour} to {new_hour}", task.desc);
+        warn_if_late(date, new_hour, &task);
         day.timeslots.entry(new_hour).or_default().push(task);
 
-        self.render(ShowSet::Date(Local::now().date_naive()))
+        self.order();
+        self.render(ShowSet::Date(self.clock.today()), &Filter::default())
     }
 
-    pub fn mark(&mut self, date: NaiveDate, hour: usize, index: usize) -> anyhow::Result<()> {
-        if let Some(task) = self
-            .days
+    /// Removes the task (or subtask) at `path`, along with its subtasks.
+    pub fn delete(&mut self, date: NaiveDate, hour: usize, path: &[usize]) -> anyhow::Result<()> {
+        let Some(task) = self.remove(date, hour, path) else {
+            fatal!("no task at {}", fmt_id(hour, path));
+        };
+        info!("deleted '{}'", task.desc);
+
+        self.render(ShowSet::Date(self.clock.today()), &Filter::default())
+    }
+
+    /// Takes the task (or subtask) at `path` out, along with its subtasks.
+    pub fn remove(&mut self, date: NaiveDate, hour: usize, path: &[usize]) -> Option<Task> {
+        let now = self.clock.now();
+        let (index, parent) = path.split_last()?;
+        if !parent.is_empty() {
+            let parent = self
+                .subtask_mut(date, hour, parent)
+                .filter(|parent| *index < parent.subtasks.len())?;
+            parent.touch(now);
+            return Some(parent.subtasks.remove(*index));
+        }
+
+        let day = self.days.get_mut(&date)?;
+        let tasks = day
+            .timeslots
+            .get_mut(&hour)
+            .filter(|tasks| *index < tasks.len())?;
+        let task = tasks.remove(*index);
+        // delete hour tasks vec if empty
+        if tasks.is_empty() {
+            day.timeslots.remove(&hour);
+        }
+        Some(task)
+    }
+
+    /// Moves the task at `index` in `hour` on `date` to `new_hour` on
+    /// `new_date`, counting it as deferred if that's later.
+    pub fn move_task(
+        &mut self,
+        date: NaiveDate,
+        hour: usize,
+        index: usize,
+        new_date: NaiveDate,
+        new_hour: usize,
+    ) -> Option<()> {
+        let mut task = self.remove(date, hour, &[index])?;
+        task.touch(self.clock.now());
+        if (new_date, new_hour) > (date, hour) {
+            task.defer();
+        }
+        self.put(Some((new_date, new_hour)), task);
+        self.order();
+        Some(())
+    }
+
+    /// Removes the task at `index` in the inbox.
+    pub fn delete_inbox(&mut self, index: usize) -> anyhow::Result<()> {
+        if index >= self.inbox.len() {
+            fatal!("no task at index {index} in the inbox");
+        }
+        let task = self.inbox.remove(index);
+        info!("deleted '{}' from the inbox", task.desc);
+
+        self.render(ShowSet::Date(self.clock.today()), &Filter::default())
+    }
+
+    /// Adds tasks from another program. Tasks with the uid of one that's
+    /// already here (because it was imported or exported before) update that
+    /// one instead, and move it if it's been rescheduled. Tasks without a uid
+    /// update the task with the same description on the same day (or in the
+    /// inbox), if there is one. Subtasks either come after their parent or
+    /// already hang off it. Returns how many tasks were added and how many
+    /// updated.
+    pub fn import(&mut self, tasks: Vec<ImportedTask>) -> (usize, usize) {
+        let now = self.clock.now();
+        let (mut added, mut updated) = (0, 0);
+        for ImportedTask {
+            mut task,
+            slot,
+            parent,
+        } in tasks
+        {
+            task.walk_mut(&mut |task| {
+                task.created.get_or_insert(now);
+                task.modified.get_or_insert(now);
+            });
+            let existing = match &task.uid {
+                Some(uid) => self.locate(uid),
+                None => self.locate_same(&task.desc, slot),
+            };
+            if let Some(place) = existing {
+                self.place_mut(&place).unwrap().update_from(task, now);
+                let moved = match &place {
+                    Place::Slot(date, hour, path) => {
+                        path.len() == 1 && slot != Some((*date, *hour))
+                    }
+                    Place::Inbox(path) => path.len() == 1 && slot.is_some(),
+                };
+                if moved {
+                    let task = self.take(place);
+                    self.put(slot, task);
+                }
+                updated += 1;
+                continue;
+            }
+
+            match parent.and_then(|uid| self.locate(&uid)) {
+                // Not add_subtask, which would undo a finished parent
+                Some(place) => self.place_mut(&place).unwrap().subtasks.push(task),
+                None => self.put(slot, task),
+            }
+            added += 1;
+        }
+
+        self.assign_ids();
+        self.order();
+        (added, updated)
+    }
+
+    fn locate(&self, uid: &str) -> Option<Place> {
+        fn find(task: &Task, uid: &str, path: &mut Vec<usize>) -> bool {
+            if task.uid() == uid {
+                return true;
+            }
+            for (index, subtask) in task.subtasks.iter().enumerate() {
+                path.push(index);
+                if find(subtask, uid, path) {
+                    return true;
+                }
+                path.pop();
+            }
+            false
+        }
+
+        for (date, schedule) in &self.days {
+            for (hour, tasks) in &schedule.timeslots {
+                for (index, task) in tasks.iter().enumerate() {
+                    let mut path = vec![index];
+                    if find(task, uid, &mut path) {
+                        return Some(Place::Slot(*date, *hour, path));
+                    }
+                }
+            }
+        }
+        for (index, task) in self.inbox.iter().enumerate() {
+            let mut path = vec![index];
+            if find(task, uid, &mut path) {
+                return Some(Place::Inbox(path));
+            }
+        }
+        None
+    }
+
+    /// The top-level task with description `desc` on the day of `slot`,
+    /// preferring the same hour, or in the inbox.
+    fn locate_same(&self, desc: &str, slot: Option<(NaiveDate, usize)>) -> Option<Place> {
+        // Tasks without an id were only just imported, and could have the
+        // same description on purpose
+        let same = |task: &Task| task.id != 0 && task.desc == desc;
+        let Some((date, hour)) = slot else {
+            let index = self.inbox.iter().position(same)?;
+            return Some(Place::Inbox(vec![index]));
+        };
+        let timeslots = &self.days.get(&date)?.timeslots;
+        let mut hours: Vec<_> = timeslots.keys().copied().collect();
+        hours.sort_by_key(|h| *h != hour);
+        hours.into_iter().find_map(|hour| {
+            let index = timeslots[&hour].iter().position(same)?;
+            Some(Place::Slot(date, hour, vec![index]))
+        })
+    }
+
+    fn place_mut(&mut self, place: &Place) -> Option<&mut Task> {
+        match place {
+            Place::Slot(date, hour, path) => self.subtask_mut(*date, *hour, path),
+            Place::Inbox(path) => {
+                let (index, rest) = path.split_first()?;
+                self.inbox.get_mut(*index)?.subtask_mut(rest)
+            }
+        }
+    }
+
+    /// Removes the top-level task at `place`.
+    fn take(&mut self, place: Place) -> Task {
+        match place {
+            Place::Slot(date, hour, path) => {
+                let day = self.days.get_mut(&date).unwrap();
+                let tasks = day.timeslots.get_mut(&hour).unwrap();
+                let task = tasks.remove(path[0]);
+                if tasks.is_empty() {
+                    day.timeslots.remove(&hour);
+                }
+                task
+            }
+            Place::Inbox(path) => self.inbox.remove(path[0]),
+        }
+    }
+
+    /// Puts `task` at `slot`, or in the inbox, without touching it.
+    pub fn put(&mut self, slot: Option<(NaiveDate, usize)>, task: Task) {
+        match slot {
+            Some((date, hour)) => self
+                .days
+                .entry(date)
+                .or_default()
+                .timeslots
+                .entry(hour)
+                .or_default()
+                .push(task),
+            None => self.inbox.push(task),
+        }
+    }
+
+    pub fn task(&self, date: NaiveDate, hour: usize, index: usize) -> Option<&Task> {
+        self.days
+            .get(&date)
+            .map(|schedule| &schedule.timeslots)
+            .and_then(|slot| slot.get(&hour))
+            .and_then(|hour| hour.get(index))
+    }
+
+    pub fn task_mut(&mut self, date: NaiveDate, hour: usize, index: usize) -> Option<&mut Task> {
+        self.days
             .get_mut(&date)
             .map(|schedule| &mut schedule.timeslots)
             .and_then(|slot| slot.get_mut(&hour))
             .and_then(|hour| hour.get_mut(index))
-        {
-            task.mark_complete();
-            info!("marked '{}' complete", task.desc);
+    }
+
+    /// The task at `path`, which starts with the task's index in its hour and
+    /// continues with subtask indices.
+    pub fn subtask(&self, date: NaiveDate, hour: usize, path: &[usize]) -> Option<&Task> {
+        let (index, rest) = path.split_first()?;
+        self.task(date, hour, *index)?.subtask(rest)
+    }
+
+    pub fn subtask_mut(
+        &mut self,
+        date: NaiveDate,
+        hour: usize,
+        path: &[usize],
+    ) -> Option<&mut Task> {
+        let (index, rest) = path.split_first()?;
+        self.task_mut(date, hour, *index)?.subtask_mut(rest)
+    }
+
+    pub fn mark(&mut self, date: NaiveDate, hour: usize, path: &[usize]) -> anyhow::Result<()> {
+        if let Some(task) = self.subtask(date, hour, path) {
+            let blockers = self.blockers(task);
+            if !task.completed && !blockers.is_empty() {
+                let blockers: Vec<_> = blockers.iter().map(|t| format!("'{}'", t.desc)).collect();
+                warn!(
+                    "'{}' is still waiting on {}",
+                    task.desc,
+                    blockers.join(", ")
+                );
+            }
+        }
+        let now = self.clock.now();
+        let marked = path
+            .split_first()
+            .and_then(|(index, rest)| self.task_mut(date, hour, *index)?.mark_path(rest, now));
+        if let Some(desc) = marked {
+            info!("marked '{desc}' complete");
         }
 
-        self.render(ShowSet::Date(Local::now().date_naive()))
+        self.order();
+        self.render(ShowSet::Date(self.clock.today()), &Filter::default())
     }
 
-    pub fn show(&self, set: ShowSet) {
+    /// Sets or clears the deadline of the task at `path`.
+    pub fn due(
+        &mut self,
+        date: NaiveDate,
+        hour: usize,
+        path: &[usize],
+        deadline: Option<Deadline>,
+    ) -> anyhow::Result<()> {
+        let now = self.clock.now();
+        let Some(task) = self.subtask_mut(date, hour, path) else {
+            fatal!("no task at {}", fmt_id(hour, path));
+        };
+        task.deadline = deadline;
+        task.touch(now);
+        match deadline {
+            Some(deadline) => info!("'{}' is due {deadline}", task.desc),
+            None => info!("'{}' no longer has a deadline", task.desc),
+        }
+        warn_if_late(date, hour, task);
+
+        self.render(ShowSet::Date(self.clock.today()), &Filter::default())
+    }
+
+    /// Unfinished tasks matching `filter` that are due within the next
+    /// [`UPCOMING_DAYS`], or already should have been done, soonest first.
+    pub fn upcoming_deadlines(&self, filter: &Filter) -> Vec<&Task> {
+        let until = self.clock.today() + Days::new(UPCOMING_DAYS);
+        let mut tasks: Vec<_> = self
+            .tasks()
+            .into_iter()
+            .filter(|task| !task.completed && filter.matches(task))
+            .filter(|task| task.deadline.is_some_and(|d| d.date <= until))
+            .collect();
+        tasks.sort_by_key(|task| task.deadline.map(|d| (d.date, d.hour())));
+        tasks
+    }
+
+    pub fn remind(&mut self, date: NaiveDate, hour: usize, index: usize, minutes: u32) {
+        let now = self.clock.now();
+        let Some(task) = self.task_mut(date, hour, index) else {
+            fatal!("no task at {hour}.{index}");
+        };
+        task.remind = Some(minutes);
+        task.touch(now);
+        info!("will remind about '{}' {minutes} minutes ahead", task.desc);
+    }
+
+    /// Edits the task's note, links and checklist in `$EDITOR`.
+    pub fn note(&mut self, date: NaiveDate, hour: usize, path: &[usize]) -> anyhow::Result<()> {
+        let now = self.clock.now();
+        let Some(task) = self.subtask_mut(date, hour, path) else {
+            fatal!("no task at {}", fmt_id(hour, path));
+        };
+        let details = task.details.edit(&task.desc)?;
+        if details == task.details {
+            info!("no changes to '{}'", task.desc);
+            return Ok(());
+        }
+        task.details = details;
+        task.touch(now);
+        info!("updated notes for '{}'", task.desc);
+
+        self.render(ShowSet::Date(self.clock.today()), &Filter::default())
+    }
+
+    pub fn show(&self, set: ShowSet, filter: &Filter, verbose: bool) {
         // avoid extra newline
-        print!("{}", KeeperDisplay::new(self, set, ColorStyle::Color));
+        let display =
+            KeeperDisplay::new(self, set, filter.clone(), ColorStyle::Color).verbose(verbose);
+        print!("{display}");
+    }
+
+    pub fn list(&self, filter: &Filter) {
+        print!("{}", TaskList::new(self, filter.clone(), ColorStyle::Color));
+    }
+
+    pub fn history(&self, set: ShowSet, filter: &Filter) {
+        print!(
+            "{}",
+            History::new(self, set, filter.clone(), ColorStyle::Color)
+        );
     }
 
-    pub fn render(&self, set: ShowSet) -> anyhow::Result<()> {
+    pub fn search(&self, search: &Search) {
+        let hits = search.hits(self);
+        if hits.is_empty() {
+            info!("no matching tasks");
+            return;
+        }
+        print!("{}", SearchResults::new(self, hits, ColorStyle::Color));
+    }
+
+    pub fn inbox_list(&self, filter: &Filter) {
+        print!(
+            "{}",
+            InboxList::new(self, filter.clone(), ColorStyle::Color)
+        );
+    }
+
+    /// Renders the wallpapers. An empty `filter` means whatever the config
+    /// says to show at this hour.
+    pub fn render(&self, set: ShowSet, filter: &Filter) -> anyhow::Result<()> {
+        if self.defer_render {
+            return Ok(());
+        }
+        self.render_now(set, filter)
+    }
+
+    /// Like [`Keeper::render`], even if rendering has been deferred.
+    pub fn render_now(&self, set: ShowSet, filter: &Filter) -> anyhow::Result<()> {
         // delete old wall papers
         let wallpapers_dir = &format!("{HOME}/.local/share/keeper/wallpapers/");
         process::Command::new(FD)
@@ -147,64 +1228,141 @@ impl Keeper {
             .output()
             .with_context(|| format!("failed to delete old wallpapers from {wallpapers_dir}"))?;
 
-        // create new one
-        let today = Local::now();
-        let wallpaper_file = format!(
-            "{HOME}/.local/share/keeper/wallpapers/wallpaper-{}.png",
-            today.format("%y-%m-%d-%H-%M-%S")
-        );
+        // create new ones, one per monitor
+        let today = self.clock.now();
+        let config = Config::load()?;
+        let fonts = Fonts::load(&config.font).context("failed to load fonts")?;
+        let filter = match filter.is_empty() {
+            true => config.wallpaper.filter_at(today.hour() as usize)?,
+            false => filter.clone(),
+        };
+        let tag_colors = config.wallpaper.tag_colors()?;
+        let mut wallpapers = Vec::new();
+        for (i, monitor) in Monitor::resolve(&config.wallpaper.monitors)
+            .into_iter()
+            .enumerate()
+        {
+            let wallpaper_file = PathBuf::from(format!(
+                "{HOME}/.local/share/keeper/wallpapers/wallpaper-{}-{}.png",
+                monitor.name.clone().unwrap_or_else(|| i.to_string()),
+                today.format("%y-%m-%d-%H-%M-%S")
+            ));
 
-        let mut renderer = KeeperRenderer::new(self, set, NORD_BG);
-        renderer.render();
-        renderer
-            .save(wallpaper_file.as_ref())
-            .with_context(|| format!("failed to save new wallpaper to {wallpaper_file:?}"))?;
+            let set = monitor.days.map_or(set, ShowSet::Days);
+            let size = (monitor.width, monitor.height);
+            let mut renderer =
+                KeeperRenderer::new(self, set, filter.clone(), NORD_BG, &fonts, size);
+            renderer.set_tag_colors(tag_colors.clone());
+            renderer.set_show_inbox(config.wallpaper.inbox);
+            renderer.render();
+            renderer
+                .save(&wallpaper_file)
+                .with_context(|| format!("failed to save new wallpaper to {wallpaper_file:?}"))?;
+            wallpapers.push((monitor, wallpaper_file));
+        }
 
-        // set new wallpaper
-        process::Command::new("automator")
-            .args([
-                "-i",
-                &wallpaper_file,
-                &format!("{HOME}/.local/share/keeper/wp.workflow"),
-            ])
-            .output()
-            .context("automator workflow failed")?;
+        // set new wallpapers
+        config.wallpaper.backend.apply(&wallpapers)?;
 
         Ok(())
     }
 }
 
-enum ColorStyle {
+/// Where a task stands, as its color in the terminal shows it. Being blocked
+/// is separate.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskStatus {
+    Done,
+    Overdue,
+    Pending,
+}
+
+impl TaskStatus {
+    /// `overdue` is whether the task is late, from [`Task::overdue`].
+    pub fn of(task: &Task, overdue: bool) -> Self {
+        match (task.completed, overdue) {
+            (true, _) => Self::Done,
+            (false, true) => Self::Overdue,
+            (false, false) => Self::Pending,
+        }
+    }
+}
+
+/// Formats a task id like `14.0.2`.
+pub fn fmt_id(hour: usize, path: &[usize]) -> String {
+    let mut id = hour.to_string();
+    for index in path {
+        id.push_str(&format!(".{index}"));
+    }
+    id
+}
+
+/// Tasks by id, from [`Keeper::index`].
+pub struct TaskIndex<'a>(HashMap<u64, &'a Task>);
+
+impl<'a> TaskIndex<'a> {
+    pub fn find(&self, id: u64) -> Option<&'a Task> {
+        self.0.get(&id).copied()
+    }
+
+    /// The unfinished tasks that `task` depends on. Tasks that no longer exist
+    /// don't count.
+    pub fn blockers(&self, task: &Task) -> Vec<&'a Task> {
+        task.depends
+            .iter()
+            .filter_map(|id| self.find(*id))
+            .filter(|dependency| !dependency.completed)
+            .collect()
+    }
+
+    /// Whether `task` can't be done yet because of unfinished dependencies.
+    pub fn blocked(&self, task: &Task) -> bool {
+        !task.completed && !self.blockers(task).is_empty()
+    }
+}
+
+pub enum ColorStyle {
     Color,
     NoColor,
 }
 
-struct KeeperDisplay<'a> {
+pub struct KeeperDisplay<'a> {
     keeper: &'a Keeper,
+    by_id: TaskIndex<'a>,
     selection: ShowSet,
+    filter: Filter,
     color: ColorStyle,
+    verbose: bool,
 }
 
 impl<'a> KeeperDisplay<'a> {
-    pub fn new(keeper: &'a Keeper, selection: ShowSet, color: ColorStyle) -> Self {
+    pub fn new(keeper: &'a Keeper, selection: ShowSet, filter: Filter, color: ColorStyle) -> Self {
         Self {
             keeper,
+            by_id: keeper.index(),
             selection,
+            filter,
             color,
+            verbose: false,
         }
     }
 
+    /// Also show each task's note, links and checklist below its hour.
+    pub fn verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
     fn fmt_day(&self, f: &mut std::fmt::Formatter<'_>, date: NaiveDate) -> std::fmt::Result {
-        let mut green = GREEN;
-        let mut red = RED;
-        let mut blue = BLUE;
-        let mut reset = RESET;
-        if matches!(self.color, ColorStyle::NoColor) {
-            green = "";
-            red = "";
-            blue = "";
-            reset = "";
-        }
+        let palette = Palette::new(&self.color);
+        let Palette {
+            green,
+            red,
+            blue,
+            reset,
+            ..
+        } = palette;
 
         writeln!(f, "{}", date.format("%d %b %Y"))?;
 
@@ -213,196 +1371,455 @@ impl<'a> KeeperDisplay<'a> {
             return Ok(());
         };
 
+        let now = self.keeper.clock.now();
         for (time, tasklist) in timeslots.iter() {
-            let all_done = tasklist.iter().all(|t| t.completed);
-            // If this hour has passed. For example, if time = 10, then we are
-            // at 11:00 o'clock or later.
-            let past_due = Local
-                .from_local_datetime(&date.and_hms_opt(*time as u32, 59, 59).unwrap())
-                .unwrap()
-                < Local::now();
-
-            let bracket_color = match (all_done, past_due) {
-                (true, true) => green,
-                (true, false) => green,
+            // Keep the real indices around for the subtask ids
+            let tasklist: Vec<_> = tasklist
+                .iter()
+                .enumerate()
+                .filter(|(_, task)| self.filter.matches(task))
+                .collect();
+            if tasklist.is_empty() {
+                continue;
+            }
+            let all_done = tasklist.iter().all(|(_, t)| t.completed);
+            let past_due = past_due(date, *time, now);
+            let overdue = tasklist
+                .iter()
+                .any(|(_, t)| !t.completed && t.overdue(past_due, now));
+
+            let bracket_color = match (all_done, overdue) {
+                (true, _) => green,
                 (false, true) => red,
                 (false, false) => blue,
             };
             write!(f, "{bracket_color}[{time}]{reset}")?;
 
-            for task in tasklist {
-                let color = match (task.completed, past_due) {
-                    (true, true) => green,
-                    (true, false) => green,
-                    (false, true) => red,
-                    (false, false) => reset,
-                };
-                write!(f, " {color}({reset}{}{color}){reset}", task.desc)?
+            for (_, task) in &tasklist {
+                write!(f, " ")?;
+                let overdue = task.overdue(past_due, now);
+                palette.fmt_task(f, task, overdue, self.by_id.blocked(task))?;
             }
 
             writeln!(f,)?;
+
+            for (index, task) in tasklist {
+                if self.verbose {
+                    palette.fmt_details(f, date, task, &self.by_id.blockers(task), 4)?;
+                }
+                self.fmt_subtasks(f, date, task, &mut vec![*time, index], past_due)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn fmt_deadlines(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let tasks = self.keeper.upcoming_deadlines(&self.filter);
+        if tasks.is_empty() {
+            return Ok(());
+        }
+        let palette = Palette::new(&self.color);
+        let Palette {
+            red, yellow, reset, ..
+        } = palette;
+        let now = self.keeper.clock.now();
+
+        writeln!(f)?;
+        writeln!(f, "Deadlines")?;
+        for task in tasks {
+            let Some(deadline) = task.deadline else {
+                continue;
+            };
+            let overdue = deadline.passed(now);
+            let color = if overdue { red } else { yellow };
+            write!(f, "{color}[{}]{reset} ", deadline.countdown(now))?;
+            palette.fmt_task(f, task, overdue, self.by_id.blocked(task))?;
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the subtasks of `task` as an indented tree, each with its id.
+    /// `id` is the hour followed by the path to `task`.
+    fn fmt_subtasks(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        date: NaiveDate,
+        task: &Task,
+        id: &mut Vec<usize>,
+        past_due: bool,
+    ) -> std::fmt::Result {
+        let palette = Palette::new(&self.color);
+        let now = self.keeper.clock.now();
+        let indent = 4 * (id.len() - 1);
+        for (index, subtask) in task.subtasks.iter().enumerate() {
+            id.push(index);
+            write!(f, "{:indent$}{} ", "", fmt_id(id[0], &id[1..]))?;
+            let overdue = subtask.overdue(past_due, now);
+            palette.fmt_task(f, subtask, overdue, self.by_id.blocked(subtask))?;
+            writeln!(f)?;
+            if self.verbose {
+                let blockers = self.by_id.blockers(subtask);
+                palette.fmt_details(f, date, subtask, &blockers, indent + 4)?;
+            }
+            self.fmt_subtasks(f, date, subtask, id, past_due)?;
+            id.pop();
         }
         Ok(())
     }
 }
 
-impl Display for KeeperDisplay<'_> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self.selection {
-            ShowSet::Days(days) => {
-                let mut days = Local::now().date_naive().iter_days().take(days);
-                let Some(date) = days.next() else {
-                    return Ok(());
-                };
+/// Terminal colors, which are all empty for [`ColorStyle::NoColor`].
+#[derive(Clone, Copy)]
+struct Palette {
+    green: &'static str,
+    red: &'static str,
+    blue: &'static str,
+    yellow: &'static str,
+    cyan: &'static str,
+    purple: &'static str,
+    reset: &'static str,
+}
 
-                // avoid a double newline at the end
-                self.fmt_day(f, date)?;
-                for date in days {
-                    writeln!(f)?;
-                    self.fmt_day(f, date)?;
-                }
+impl Palette {
+    fn new(style: &ColorStyle) -> Self {
+        match style {
+            ColorStyle::Color => Self {
+                green: GREEN,
+                red: RED,
+                blue: BLUE,
+                yellow: YELLOW,
+                cyan: CYAN,
+                purple: PURPLE,
+                reset: RESET,
+            },
+            ColorStyle::NoColor => Self {
+                green: "",
+                red: "",
+                blue: "",
+                yellow: "",
+                cyan: "",
+                purple: "",
+                reset: "",
+            },
+        }
+    }
+
+    /// Writes `(desc)`, with the parentheses colored by the task's state.
+    fn fmt_task(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        task: &Task,
+        overdue: bool,
+        blocked: bool,
+    ) -> std::fmt::Result {
+        let Self {
+            green,
+            red,
+            yellow,
+            cyan,
+            purple,
+            reset,
+            ..
+        } = *self;
+        let color = match (TaskStatus::of(task, overdue), blocked) {
+            (TaskStatus::Done, _) => green,
+            (_, true) => purple,
+            (TaskStatus::Overdue, false) => red,
+            (TaskStatus::Pending, false) => reset,
+        };
+        write!(f, "{color}({reset}")?;
+        if task.priority != Priority::Normal {
+            write!(f, "{yellow}{}{reset} ", task.priority.marker())?;
+        }
+        match blocked && !task.completed {
+            true => write!(f, "{purple}{}{reset}", task.desc)?,
+            false => write!(f, "{}", task.desc)?,
+        }
+        if task.details.has_attachments() {
+            write!(f, " 📎")?;
+        }
+        if let Some((done, total)) = task.details.progress() {
+            write!(f, " [{done}/{total}]")?;
+        }
+        if let Some((done, total)) = task.subtask_progress() {
+            write!(f, " [{done}/{total}]")?;
+        }
+        if let Some(deadline) = task.deadline {
+            let color = if overdue && !task.completed {
+                red
+            } else {
+                yellow
+            };
+            write!(f, " {color}due {deadline}{reset}")?;
+        }
+        for tag in &task.tags {
+            write!(f, " {cyan}{tag}{reset}")?;
+        }
+        write!(f, "{color}){reset}")
+    }
+
+    /// Writes when the task was done, what it's waiting on, and its note,
+    /// links and checklist, indented by `indent`. `date` is the day the task
+    /// is planned for.
+    fn fmt_details(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        date: NaiveDate,
+        task: &Task,
+        blockers: &[&Task],
+        indent: usize,
+    ) -> std::fmt::Result {
+        let Self {
+            green,
+            blue,
+            purple,
+            reset,
+            ..
+        } = *self;
+        let Details {
+            note,
+            links,
+            checklist,
+        } = &task.details;
+        let completed_at = task.completed_at.filter(|_| task.completed);
+        if task.details.is_empty() && blockers.is_empty() && completed_at.is_none() {
+            return Ok(());
+        }
+
+        let inner = indent + 4;
+        writeln!(f, "{:indent$}{}:", "", task.desc)?;
+        if let Some(at) = completed_at {
+            write!(f, "{:inner$}{green}done at {}", "", at.format("%H:%M"))?;
+            // Only worth saying when it wasn't done on the day it was planned
+            if at.date_naive() != date {
+                write!(f, " on {}", at.format("%d %b"))?;
             }
-            ShowSet::Date(date) => {
-                self.fmt_day(f, date)?;
+            writeln!(f, "{reset}")?;
+        }
+        for blocker in blockers {
+            writeln!(f, "{:inner$}{purple}waiting on{reset} {}", "", blocker.desc)?;
+        }
+        for line in note.lines() {
+            writeln!(f, "{:inner$}{line}", "")?;
+        }
+        for link in links {
+            writeln!(f, "{:inner$}{blue}{link}{reset}", "")?;
+        }
+        for ChecklistItem { done, text } in checklist {
+            match done {
+                true => writeln!(f, "{:inner$}{green}[x]{reset} {text}", "")?,
+                false => writeln!(f, "{:inner$}[ ] {text}", "")?,
             }
         }
         Ok(())
     }
 }
 
-struct KeeperRenderer<'a> {
+/// Every task matching a filter, one per line with the date and id needed to
+/// refer to it in other commands.
+pub struct TaskList<'a> {
     keeper: &'a Keeper,
-    selection: ShowSet,
-    image: ImageBuffer<Rgb<u8>, Vec<u8>>,
-    font: Font<'static>,
-    scale: Scale,
-    xpos: i32,
-    ypos: i32,
+    by_id: TaskIndex<'a>,
+    filter: Filter,
+    color: ColorStyle,
 }
 
-impl<'a> KeeperRenderer<'a> {
-    pub fn new(keeper: &'a Keeper, selection: ShowSet, background_color: Rgb<u8>) -> Self {
-        let image = ImageBuffer::from_pixel(SCREEN_WIDTH, SCREEN_HEIGHT, background_color);
-
-        let font = Font::try_from_vec(Vec::from(include_bytes!("../iosevka-regular.ttc") as &[u8]))
-            .expect("font is valid");
-
-        let displayed = KeeperDisplay::new(keeper, selection, ColorStyle::NoColor).to_string();
-        let height = displayed.lines().count() as f32;
-        let width = (displayed
-            .lines()
-            .map(|l| l.len())
-            .max()
-            .expect("there is at least one non-empty line") as f32)
-            / CHAR_HEIGHT_TO_WIDTH;
+impl<'a> TaskList<'a> {
+    pub fn new(keeper: &'a Keeper, filter: Filter, color: ColorStyle) -> Self {
+        Self {
+            keeper,
+            by_id: keeper.index(),
+            filter,
+            color,
+        }
+    }
+}
 
-        // Take padding into account
-        let effective_height = (image.height() - 2 * Y_PAD - Y_START) as f32;
-        // Subtracting another 20 pixels prevents noticeable left-right asymmetry for some reason
-        let effective_width = (image.width() - 2 * X_PAD - 20) as f32;
+impl Display for TaskList<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let palette = Palette::new(&self.color);
+        let now = self.keeper.clock.now();
+        for (date, Schedule { timeslots }) in &self.keeper.days {
+            for (time, tasklist) in timeslots {
+                for (index, task) in tasklist.iter().enumerate() {
+                    if !self.filter.matches(task) {
+                        continue;
+                    }
+                    write!(f, "{} {time}.{index} ", date.format("%d-%m-%Y"))?;
+                    let blocked = self.by_id.blocked(task);
+                    let overdue = task.overdue(past_due(*date, *time, now), now);
+                    palette.fmt_task(f, task, overdue, blocked)?;
+                    writeln!(f)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
 
-        let size = (effective_height / height).min(effective_width / width);
-        let scale = Scale { x: size, y: size };
+/// The tasks in the inbox matching a filter, with the index to use with
+/// `schedule`.
+pub struct InboxList<'a> {
+    keeper: &'a Keeper,
+    by_id: TaskIndex<'a>,
+    filter: Filter,
+    color: ColorStyle,
+}
 
+impl<'a> InboxList<'a> {
+    pub fn new(keeper: &'a Keeper, filter: Filter, color: ColorStyle) -> Self {
         Self {
             keeper,
-            selection,
-            image,
-            font,
-            scale,
-            xpos: X_PAD as i32,
-            ypos: (Y_PAD + Y_START) as i32,
+            by_id: keeper.index(),
+            filter,
+            color,
+        }
+    }
+}
+
+impl Display for InboxList<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let palette = Palette::new(&self.color);
+        let now = self.keeper.clock.now();
+        for (index, task) in self.keeper.inbox.iter().enumerate() {
+            if !self.filter.matches(task) {
+                continue;
+            }
+            write!(f, "{index} ")?;
+            // Only a deadline can make something in the inbox late
+            let overdue = task.overdue(false, now);
+            palette.fmt_task(f, task, overdue, self.by_id.blocked(task))?;
+            writeln!(f)?;
         }
+        Ok(())
     }
+}
 
-    /// Add a string containing **NO NEWLINES**.
-    fn render_literal(&mut self, color: Rgb<u8>, literal: &str) {
-        draw_text_mut(
-            &mut self.image,
+/// Tasks found by a [`Search`], one per line with the date and id needed to
+/// refer to them in other commands, or `inbox` and the index for tasks that
+/// aren't scheduled yet.
+pub struct SearchResults<'a> {
+    by_id: TaskIndex<'a>,
+    hits: Vec<Hit<'a>>,
+    color: ColorStyle,
+}
+
+impl<'a> SearchResults<'a> {
+    pub fn new(keeper: &'a Keeper, hits: Vec<Hit<'a>>, color: ColorStyle) -> Self {
+        Self {
+            by_id: keeper.index(),
+            hits,
             color,
-            self.xpos,
-            self.ypos,
-            self.scale,
-            &self.font,
-            literal,
-        );
-        let (x, _) = text_size(self.scale, &self.font, literal);
-        self.xpos += x;
+        }
     }
+}
 
-    fn render_newline(&mut self) {
-        let (_, y) = text_size(self.scale, &self.font, "[]");
-        self.xpos = X_PAD as i32;
-        self.ypos += y;
+impl Display for SearchResults<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let palette = Palette::new(&self.color);
+        for hit in &self.hits {
+            match hit.slot {
+                Some((date, hour)) => write!(
+                    f,
+                    "{} {} ",
+                    date.format("%d-%m-%Y"),
+                    fmt_id(hour, &hit.path)
+                )?,
+                None => write!(f, "inbox {} ", hit.path[0])?,
+            }
+            palette.fmt_task(f, hit.task, hit.overdue, self.by_id.blocked(hit.task))?;
+            writeln!(f)?;
+        }
+        Ok(())
     }
+}
 
-    pub fn render_day(&mut self, day: NaiveDate) {
-        // Date header
-        self.render_literal(NORD_WHITE, &format!("{}", day.format("%d %b %Y")));
-        self.render_newline();
+/// What got done on each day, going by when tasks were actually completed
+/// rather than when they were planned. [`ShowSet::Days`] counts back from
+/// today.
+pub struct History<'a> {
+    keeper: &'a Keeper,
+    selection: ShowSet,
+    filter: Filter,
+    color: ColorStyle,
+}
 
-        let Some(Schedule { timeslots }) = self.keeper.days.get(&day) else {
-            self.render_literal(NORD_WHITE, "Empty");
-            return;
-        };
+impl<'a> History<'a> {
+    pub fn new(keeper: &'a Keeper, selection: ShowSet, filter: Filter, color: ColorStyle) -> Self {
+        Self {
+            keeper,
+            selection,
+            filter,
+            color,
+        }
+    }
+}
 
-        for (time, tasklist) in timeslots.iter() {
-            let all_done = tasklist.iter().all(|t| t.completed);
-            // If this hour has passed. For example, if time = 10, then we are
-            // at 11:00 o'clock or later.
-            let past_due = Local
-                .from_local_datetime(&day.and_hms_opt(*time as u32, 59, 59).unwrap())
-                .unwrap()
-                < Local::now();
-
-            let bracket_color = match (all_done, past_due) {
-                (true, true) => NORD_GREEN,
-                (true, false) => NORD_GREEN,
-                (false, true) => NORD_RED,
-                (false, false) => NORD_BLUE,
-            };
+impl Display for History<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let palette = Palette::new(&self.color);
+        let Palette { blue, reset, .. } = palette;
+        let today = self.keeper.clock.today();
+        let dates: Vec<_> = match self.selection {
+            ShowSet::Days(days) => (0..days as u64)
+                .rev()
+                .map(|ago| today - Days::new(ago))
+                .collect(),
+            ShowSet::Date(date) => vec![date],
+        };
 
-            // Draw the [time]
-            self.render_literal(bracket_color, &format!("[{time}]"));
+        let mut done: Vec<_> = self
+            .keeper
+            .tasks()
+            .into_iter()
+            .filter(|task| task.completed && self.filter.matches(task))
+            .filter_map(|task| Some((task.completed_at?, task)))
+            .collect();
+        done.sort_by_key(|(at, _)| *at);
 
-            for task in tasklist {
-                let paren_color = match (task.completed, past_due) {
-                    (true, true) => NORD_GREEN,
-                    (true, false) => NORD_GREEN,
-                    (false, true) => NORD_RED,
-                    (false, false) => NORD_WHITE,
-                };
-                self.render_literal(paren_color, " (");
-                self.render_literal(NORD_WHITE, &task.desc);
-                self.render_literal(paren_color, ")");
+        for (i, date) in dates.into_iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "{}", date.format("%d %b %Y"))?;
+            let mut tasks = done
+                .iter()
+                .filter(|(at, _)| at.date_naive() == date)
+                .peekable();
+            if tasks.peek().is_none() {
+                writeln!(f, "Nothing done")?;
+            }
+            for (at, task) in tasks {
+                write!(f, "{blue}{}{reset} ", at.format("%H:%M"))?;
+                palette.fmt_task(f, task, false, false)?;
+                writeln!(f)?;
             }
-            self.render_newline();
         }
+        Ok(())
     }
+}
 
-    pub fn render(&mut self) {
+impl Display for KeeperDisplay<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self.selection {
             ShowSet::Days(days) => {
-                let mut days = Local::now().date_naive().iter_days().take(days);
-
-                // avoid a double newline at the end
-                if let Some(date) = days.next() {
-                    self.render_day(date);
+                let mut days = self.keeper.clock.today().iter_days().take(days);
+                let Some(date) = days.next() else {
+                    return Ok(());
                 };
 
+                // avoid a double newline at the end
+                self.fmt_day(f, date)?;
                 for date in days {
-                    self.render_day(date);
+                    writeln!(f)?;
+                    self.fmt_day(f, date)?;
                 }
             }
             ShowSet::Date(date) => {
-                self.render_day(date);
+                self.fmt_day(f, date)?;
             }
         }
-    }
-
-    pub fn save(&mut self, path: &Path) -> anyhow::Result<()> {
-        self.image
-            .save(path)
-            .with_context(|| format!("failed to save image to {path:?}"))
+        self.fmt_deadlines(f)
     }
 }
@@ -0,0 +1,130 @@
+use std::{env, fs, process};
+
+use anyhow::{ensure, Context};
+use serde::{Deserialize, Serialize};
+
+/// How the help at the top of the file starts, so it can be told apart from
+/// anything the user writes (Markdown headings included).
+const HELP_START: &str = "<!-- keeper:";
+const HELP: &str = "\
+<!-- keeper: notes for '{desc}'.
+Lines starting with - [ ] or - [x] are checklist items, lines starting with
+http:// or https:// are links, and everything else is the note.
+This comment is removed when you save. -->
+";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ChecklistItem {
+    pub done: bool,
+    pub text: String,
+}
+
+/// Everything about a task that doesn't fit in its one-line description.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct Details {
+    pub note: String,
+    pub links: Vec<String>,
+    pub checklist: Vec<ChecklistItem>,
+}
+
+impl Details {
+    pub fn is_empty(&self) -> bool {
+        self.note.is_empty() && self.links.is_empty() && self.checklist.is_empty()
+    }
+
+    /// Whether there's a note or links, which get a 📎 in compact displays.
+    pub fn has_attachments(&self) -> bool {
+        !self.note.is_empty() || !self.links.is_empty()
+    }
+
+    /// How many checklist items are done, out of how many.
+    pub fn progress(&self) -> Option<(usize, usize)> {
+        if self.checklist.is_empty() {
+            return None;
+        }
+        let done = self.checklist.iter().filter(|item| item.done).count();
+        Some((done, self.checklist.len()))
+    }
+
+    /// The text that gets edited by `keeper-todo note`.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for item in &self.checklist {
+            let mark = if item.done { 'x' } else { ' ' };
+            text.push_str(&format!("- [{mark}] {}\n", item.text));
+        }
+        for link in &self.links {
+            text.push_str(link);
+            text.push('\n');
+        }
+        if !self.note.is_empty() {
+            text.push_str(&self.note);
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Inverse of [`Details::to_text`].
+    pub fn parse(text: &str) -> Self {
+        let mut details = Self::default();
+        let mut note = Vec::new();
+        let mut in_help = false;
+        for line in text.lines() {
+            if line.trim_start().starts_with(HELP_START) {
+                in_help = true;
+            }
+            if in_help {
+                in_help = !line.contains("-->");
+                continue;
+            }
+            let trimmed = line.trim();
+            if let Some(item) = trimmed.strip_prefix("- [ ]") {
+                details.checklist.push(ChecklistItem {
+                    done: false,
+                    text: item.trim().to_string(),
+                });
+            } else if let Some(item) = trimmed
+                .strip_prefix("- [x]")
+                .or_else(|| trimmed.strip_prefix("- [X]"))
+            {
+                details.checklist.push(ChecklistItem {
+                    done: true,
+                    text: item.trim().to_string(),
+                });
+            } else if trimmed.starts_with("http://") || trimmed.starts_with("https://") {
+                details.links.push(trimmed.to_string());
+            } else {
+                note.push(line.trim_end());
+            }
+        }
+        // Blank lines around the note are just noise from editing
+        details.note = note.join("\n").trim_matches('\n').to_string();
+        details
+    }
+
+    /// Opens the details in `$VISUAL`/`$EDITOR` and returns the edited version.
+    pub fn edit(&self, desc: &str) -> anyhow::Result<Self> {
+        let editor = env::var("VISUAL")
+            .or_else(|_| env::var("EDITOR"))
+            .unwrap_or_else(|_| "vi".to_string());
+        let path = env::temp_dir().join(format!("keeper-note-{}.md", process::id()));
+        // A `-->` in the description would end the help early
+        let help = HELP.replace("{desc}", &desc.replace("-->", "->"));
+        let contents = format!("{help}{}", self.to_text());
+        fs::write(&path, contents).with_context(|| format!("failed to write {path:?}"))?;
+
+        // Through the shell, like git does, so that e.g. EDITOR="code --wait" works
+        let status = process::Command::new("sh")
+            .args(["-c", &format!("{editor} \"$@\""), &editor])
+            .arg(&path)
+            .status()
+            .with_context(|| format!("failed to run editor '{editor}'"));
+        let edited = fs::read_to_string(&path);
+        let _ = fs::remove_file(&path);
+
+        ensure!(status?.success(), "editor '{editor}' failed");
+        let edited = edited.with_context(|| format!("failed to read {path:?}"))?;
+        Ok(Self::parse(&edited))
+    }
+}
@@ -0,0 +1,150 @@
+use anyhow::{bail, ensure};
+
+use crate::data::{Priority, Task};
+
+/// Which tasks to show.
+#[derive(Debug, Default, Clone)]
+pub struct Filter {
+    /// Only tasks at least this important.
+    pub priority: Option<Priority>,
+    /// Only tasks whose tags match this expression.
+    pub tags: Option<Expr>,
+}
+
+impl Filter {
+    pub fn is_empty(&self) -> bool {
+        self.priority.is_none() && self.tags.is_none()
+    }
+
+    pub fn matches(&self, task: &Task) -> bool {
+        self.priority
+            .is_none_or(|priority| task.priority() <= priority)
+            && self.tags.as_ref().is_none_or(|expr| expr.matches(task))
+    }
+}
+
+/// Whether a word in a description is a `+project` or `@context` tag.
+pub fn is_tag(word: &str) -> bool {
+    word.len() > 1 && (word.starts_with('+') || word.starts_with('@'))
+}
+
+/// A boolean expression over tags, e.g. `+work and not (@phone or @errand)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Tag(String),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    /// Parses an expression. `not`/`!` binds tightest, then `and`/`&`, then
+    /// `or`/`|`. Tags next to each other without an operator are and-ed.
+    pub fn parse(s: &str) -> anyhow::Result<Self> {
+        let tokens = tokenize(s);
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.or()?;
+        if let Some(token) = parser.peek() {
+            bail!("unexpected '{token}' in filter '{s}'");
+        }
+        Ok(expr)
+    }
+
+    pub fn matches(&self, task: &Task) -> bool {
+        match self {
+            Expr::Tag(tag) => task.tags().iter().any(|t| t.eq_ignore_ascii_case(tag)),
+            Expr::Not(e) => !e.matches(task),
+            Expr::And(a, b) => a.matches(task) && b.matches(task),
+            Expr::Or(a, b) => a.matches(task) || b.matches(task),
+        }
+    }
+}
+
+fn tokenize(s: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    for c in s.chars() {
+        if c.is_whitespace() || matches!(c, '(' | ')') {
+            if !current.is_empty() {
+                tokens.push(std::mem::take(&mut current));
+            }
+            if !c.is_whitespace() {
+                tokens.push(c.to_string());
+            }
+        } else if c == '!' && current.is_empty() {
+            tokens.push(c.to_string());
+        } else {
+            current.push(c);
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+struct Parser {
+    tokens: Vec<String>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(String::as_str)
+    }
+
+    fn next(&mut self) -> Option<String> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn or(&mut self) -> anyhow::Result<Expr> {
+        let mut expr = self.and()?;
+        while matches!(self.peek(), Some("or" | "|" | "||")) {
+            self.next();
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> anyhow::Result<Expr> {
+        let mut expr = self.not()?;
+        loop {
+            match self.peek() {
+                Some("and" | "&" | "&&") => {
+                    self.next();
+                }
+                // Implicit and, e.g. `+work @office`
+                Some(token) if token != ")" && !matches!(token, "or" | "|" | "||") => {}
+                _ => break,
+            }
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+        Ok(expr)
+    }
+
+    fn not(&mut self) -> anyhow::Result<Expr> {
+        if matches!(self.peek(), Some("not" | "!")) {
+            self.next();
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        self.atom()
+    }
+
+    fn atom(&mut self) -> anyhow::Result<Expr> {
+        let Some(token) = self.next() else {
+            bail!("filter ended unexpectedly");
+        };
+        if token == "(" {
+            let expr = self.or()?;
+            ensure!(self.next().as_deref() == Some(")"), "missing ')' in filter");
+            return Ok(expr);
+        }
+        ensure!(
+            is_tag(&token),
+            "expected a +project or @context in filter, found '{token}'"
+        );
+        Ok(Expr::Tag(token))
+    }
+}
@@ -0,0 +1,195 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+use anyhow::{anyhow, ensure, Context};
+use rusttype::{point, Font, GlyphId, Scale};
+
+use crate::config::FontConfig;
+use keeper_util::warn;
+
+const EMBEDDED: &[u8] = include_bytes!("../iosevka-regular.ttc");
+/// fontconfig aliases, which never match the family they resolve to.
+const GENERIC: &[&str] = &["monospace", "sans-serif", "serif", "sans", "mono"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Face {
+    #[default]
+    Regular,
+    Bold,
+    Italic,
+}
+
+/// The fonts used to draw the wallpaper. Each glyph is drawn with the first
+/// font in the chain for its face that actually has it: the face's own font,
+/// then the regular font, then the fallbacks in order.
+pub struct Fonts {
+    regular: Font<'static>,
+    bold: Option<Font<'static>>,
+    italic: Option<Font<'static>>,
+    fallback: Vec<Font<'static>>,
+}
+
+impl Fonts {
+    pub fn load(config: &FontConfig) -> anyhow::Result<Self> {
+        let mut fallback = config
+            .fallback
+            .iter()
+            .map(|spec| load(spec))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let regular = match &config.regular {
+            Some(spec) => {
+                // Keep the embedded font around, it covers a lot of symbols
+                fallback.push(embedded());
+                load(spec)?
+            }
+            None => embedded(),
+        };
+
+        Ok(Self {
+            regular,
+            bold: config.bold.as_deref().map(load).transpose()?,
+            italic: config.italic.as_deref().map(load).transpose()?,
+            fallback,
+        })
+    }
+
+    /// Just the font at `path` for everything, without the embedded one. The
+    /// tests draw with this so their reference images don't depend on which
+    /// Iosevka build gets embedded.
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        Ok(Self {
+            regular: load_file(path)?,
+            bold: None,
+            italic: None,
+            fallback: Vec::new(),
+        })
+    }
+
+    fn chain(&self, face: Face) -> impl Iterator<Item = &Font<'static>> {
+        let styled = match face {
+            Face::Regular => None,
+            Face::Bold => self.bold.as_ref(),
+            Face::Italic => self.italic.as_ref(),
+        };
+        styled
+            .into_iter()
+            .chain([&self.regular])
+            .chain(self.fallback.iter())
+    }
+
+    /// Whether `face` has to be faked when drawing with `font`, because it
+    /// isn't that face's own font.
+    pub fn synthetic(&self, face: Face, font: &Font<'static>) -> bool {
+        let own = match face {
+            Face::Regular => return false,
+            Face::Bold => self.bold.as_ref(),
+            Face::Italic => self.italic.as_ref(),
+        };
+        !own.is_some_and(|own| std::ptr::eq(own, font))
+    }
+
+    /// Splits `text` into runs that can each be drawn with a single font.
+    pub fn runs<'t>(&self, face: Face, text: &'t str) -> Vec<(&Font<'static>, &'t str)> {
+        let mut runs: Vec<(&Font<'static>, &'t str)> = Vec::new();
+        let mut start = 0;
+        for (i, c) in text.char_indices() {
+            // Whitespace looks the same in every font, so don't break runs on it
+            let font = match (c.is_whitespace(), runs.last()) {
+                (true, Some(&(font, _))) => font,
+                _ => self
+                    .chain(face)
+                    .find(|font| font.glyph(c).id() != GlyphId(0))
+                    .unwrap_or_else(|| self.chain(face).next().unwrap()),
+            };
+            match runs.last_mut() {
+                Some((last, run)) if std::ptr::eq(*last, font) => {
+                    *run = &text[start..i + c.len_utf8()];
+                }
+                _ => {
+                    start = i;
+                    runs.push((font, &text[i..i + c.len_utf8()]));
+                }
+            }
+        }
+        runs
+    }
+
+    /// Whether any font in the chain for `face` can draw `c`.
+    pub fn has_glyph(&self, face: Face, c: char) -> bool {
+        self.chain(face)
+            .any(|font| font.glyph(c).id() != GlyphId(0))
+    }
+
+    /// Horizontal advance of `text` in pixels, including trailing whitespace and
+    /// kerning.
+    pub fn advance(&self, face: Face, scale: Scale, text: &str) -> f32 {
+        self.runs(face, text)
+            .into_iter()
+            .map(|(font, run)| {
+                font.layout(run, scale, point(0.0, 0.0))
+                    .last()
+                    .map(|glyph| {
+                        glyph.position().x + glyph.unpositioned().h_metrics().advance_width
+                    })
+                    .unwrap_or(0.0)
+            })
+            .sum()
+    }
+
+    pub fn ascent(&self, scale: Scale) -> f32 {
+        self.regular.v_metrics(scale).ascent
+    }
+
+    pub fn line_height(&self, scale: Scale) -> f32 {
+        let v_metrics = self.regular.v_metrics(scale);
+        v_metrics.ascent - v_metrics.descent + v_metrics.line_gap
+    }
+}
+
+fn embedded() -> Font<'static> {
+    Font::try_from_bytes(EMBEDDED).expect("font is valid")
+}
+
+/// Loads a font from a file, or from a fontconfig pattern if `spec` isn't a
+/// file. fc-match always comes up with something, so say so when it isn't the
+/// family that was asked for.
+fn load(spec: &str) -> anyhow::Result<Font<'static>> {
+    let path = PathBuf::from(spec);
+    let path = if path.is_file() {
+        path
+    } else {
+        let output = process::Command::new("fc-match")
+            .args(["--format=%{family}\n%{file}", spec])
+            .output()
+            .with_context(|| format!("failed to run fc-match for font '{spec}'"))?;
+        ensure!(
+            output.status.success(),
+            "fc-match could not find font '{spec}'"
+        );
+        let output = String::from_utf8(output.stdout).context("fc-match output is not utf-8")?;
+        let (families, file) = output
+            .split_once('\n')
+            .ok_or_else(|| anyhow!("unexpected fc-match output for font '{spec}'"))?;
+        // The family is everything before the first `:`, like `Iosevka:bold`
+        let wanted = spec.split(':').next().unwrap_or_default().trim();
+        let generic = GENERIC.iter().any(|g| g.eq_ignore_ascii_case(wanted));
+        let found = families
+            .split(',')
+            .any(|family| family.trim().eq_ignore_ascii_case(wanted));
+        if !generic && !found {
+            warn!("font '{spec}' isn't installed, fc-match picked '{families}' instead");
+        }
+        PathBuf::from(file)
+    };
+
+    load_file(&path)
+}
+
+fn load_file(path: &Path) -> anyhow::Result<Font<'static>> {
+    let bytes = fs::read(path).with_context(|| format!("failed to read font from {path:?}"))?;
+    Font::try_from_vec(bytes).ok_or_else(|| anyhow!("{path:?} is not a valid font"))
+}
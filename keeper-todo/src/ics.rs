@@ -0,0 +1,407 @@
+//! iCalendar (RFC 5545) import and export. Tasks become VTODOs, or VEVENTs for
+//! calendars that don't show todos, each lasting their hour.
+
+use anyhow::{anyhow, bail, Context};
+use chrono::{
+    DateTime, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike, Utc,
+};
+use keeper_util::warn;
+
+use crate::{
+    data::{Deadline, ImportedTask, Keeper, Priority, Task, ALL_DAY_HOUR},
+    details::Details,
+};
+
+const PRODID: &str = "-//keeper//keeper-todo//EN";
+/// Lines longer than this many bytes get folded.
+const LINE_LIMIT: usize = 75;
+
+/// Writes the tasks planned between `from` and `to` (inclusive, and either
+/// open-ended) as a calendar. Without a range, the inbox is included too.
+/// With `events`, tasks are written as VEVENTs, which leaves out the inbox,
+/// deadlines and whether tasks are done.
+pub fn export(
+    keeper: &Keeper,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    events: bool,
+) -> String {
+    let mut writer = Writer {
+        out: String::new(),
+        now: keeper.clock().now(),
+        events,
+    };
+    writer.line("BEGIN:VCALENDAR");
+    writer.line("VERSION:2.0");
+    writer.line(&format!("PRODID:{PRODID}"));
+    for (date, schedule) in &keeper.days {
+        if from.is_some_and(|from| *date < from) || to.is_some_and(|to| *date > to) {
+            continue;
+        }
+        for (hour, tasks) in &schedule.timeslots {
+            for task in tasks {
+                writer.task(task, Some((*date, *hour)), None);
+            }
+        }
+    }
+    if from.is_none() && to.is_none() && !events {
+        for task in &keeper.inbox {
+            writer.task(task, None, None);
+        }
+    }
+    writer.line("END:VCALENDAR");
+    writer.out
+}
+
+struct Writer {
+    out: String,
+    now: DateTime<Local>,
+    events: bool,
+}
+
+impl Writer {
+    /// Writes a content line, folding it if it's too long.
+    fn line(&mut self, line: &str) {
+        let mut width = 0;
+        for c in line.chars() {
+            if width + c.len_utf8() > LINE_LIMIT {
+                self.out.push_str("\r\n ");
+                width = 1;
+            }
+            self.out.push(c);
+            width += c.len_utf8();
+        }
+        self.out.push_str("\r\n");
+    }
+
+    /// Writes `task` and then its subtasks, which point back at it.
+    fn task(&mut self, task: &Task, slot: Option<(NaiveDate, usize)>, parent: Option<&Task>) {
+        let kind = if self.events { "VEVENT" } else { "VTODO" };
+        self.line(&format!("BEGIN:{kind}"));
+        self.line(&format!("UID:{}", escape(&task.uid())));
+        self.line(&format!("DTSTAMP:{}", utc(self.now)));
+        if let Some(created) = task.created() {
+            self.line(&format!("CREATED:{}", utc(created)));
+        }
+        if let Some(modified) = task.modified() {
+            self.line(&format!("LAST-MODIFIED:{}", utc(modified)));
+        }
+        self.line(&format!("SUMMARY:{}", escape(task.desc())));
+        if let Some((date, hour)) = slot {
+            self.line(&format!("DTSTART:{}", floating(date, hour, 0)));
+        }
+        let deadline = task.deadline().filter(|_| !self.events);
+        match deadline {
+            // A todo can't have both a due time and a duration
+            Some(Deadline { date, hour: None }) => {
+                self.line(&format!("DUE;VALUE=DATE:{}", date.format("%Y%m%d")))
+            }
+            Some(Deadline {
+                date,
+                hour: Some(hour),
+            }) => self.line(&format!("DUE:{}", floating(date, hour, 59))),
+            None if slot.is_some() => self.line("DURATION:PT1H"),
+            None => {}
+        }
+        if !self.events {
+            match task.completed() {
+                true => self.line("STATUS:COMPLETED"),
+                false => self.line("STATUS:NEEDS-ACTION"),
+            }
+            if let Some(at) = task.completed_at().filter(|_| task.completed()) {
+                self.line(&format!("COMPLETED:{}", utc(at)));
+            }
+        }
+        let priority = match task.priority() {
+            Priority::Urgent => Some(1),
+            Priority::High => Some(3),
+            Priority::Normal => None,
+            Priority::Low => Some(9),
+        };
+        if let Some(priority) = priority {
+            self.line(&format!("PRIORITY:{priority}"));
+        }
+        if !task.tags().is_empty() {
+            let tags: Vec<_> = task.tags().iter().map(|tag| escape(tag)).collect();
+            self.line(&format!("CATEGORIES:{}", tags.join(",")));
+        }
+        let details = task.details().to_text();
+        if !details.is_empty() {
+            self.line(&format!("DESCRIPTION:{}", escape(details.trim_end())));
+        }
+        if let Some(parent) = parent {
+            self.line(&format!("RELATED-TO:{}", escape(&parent.uid())));
+        }
+        self.line(&format!("END:{kind}"));
+
+        for subtask in task.subtasks() {
+            self.task(subtask, slot, Some(task));
+        }
+    }
+}
+
+fn utc(time: DateTime<Local>) -> String {
+    time.with_timezone(&Utc)
+        .format("%Y%m%dT%H%M%SZ")
+        .to_string()
+}
+
+/// A local time without a timezone, which calendars show at that time
+/// wherever they are.
+fn floating(date: NaiveDate, hour: usize, minute: u32) -> String {
+    let second = if minute == 59 { 59 } else { 0 };
+    let time = NaiveTime::from_hms_opt(hour as u32, minute, second).unwrap();
+    date.and_time(time).format("%Y%m%dT%H%M%S").to_string()
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => unescaped.push('\n'),
+            Some(c) => unescaped.push(c),
+            None => {}
+        }
+    }
+    unescaped
+}
+
+/// Splits `text` on `separator`s that aren't escaped with a backslash.
+fn split_escaped(text: &str, separator: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut escaped = false;
+    for c in text.chars() {
+        if c == separator && !escaped {
+            parts.push(String::new());
+            continue;
+        }
+        escaped = c == '\\' && !escaped;
+        parts.last_mut().unwrap().push(c);
+    }
+    parts.iter().map(|part| unescape(part)).collect()
+}
+
+/// A content line like `DUE;VALUE=DATE:20240201`.
+#[derive(Debug)]
+struct Property {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn parse(line: &str) -> anyhow::Result<Self> {
+        // Parameter values can be quoted, and then contain ':' and ';'
+        let mut quoted = false;
+        let mut parts = vec![];
+        let mut start = 0;
+        let mut value = None;
+        for (i, c) in line.char_indices() {
+            match c {
+                '"' => quoted = !quoted,
+                ';' if !quoted => {
+                    parts.push(&line[start..i]);
+                    start = i + 1;
+                }
+                ':' if !quoted => {
+                    parts.push(&line[start..i]);
+                    value = Some(&line[i + 1..]);
+                    break;
+                }
+                _ => {}
+            }
+        }
+        let Some(value) = value else {
+            bail!("expected a ':' in '{line}'");
+        };
+        let name = parts[0].to_uppercase();
+        let params = parts[1..]
+            .iter()
+            .filter_map(|param| param.split_once('='))
+            .map(|(key, value)| (key.to_uppercase(), value.trim_matches('"').to_string()))
+            .collect();
+        Ok(Self {
+            name,
+            params,
+            value: value.to_string(),
+        })
+    }
+
+    fn param(&self, key: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The date, and the local time unless it's a whole day.
+    fn time(&self) -> anyhow::Result<(NaiveDate, Option<NaiveTime>)> {
+        let value = self.value.trim();
+        if self.param("VALUE") == Some("DATE") || value.len() == 8 {
+            let date = NaiveDate::parse_from_str(value, "%Y%m%d")
+                .with_context(|| format!("invalid date '{value}' in {}", self.name))?;
+            return Ok((date, None));
+        }
+        let time = match value.strip_suffix('Z') {
+            Some(value) => {
+                let utc = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+                    .with_context(|| format!("invalid time '{value}' in {}", self.name))?;
+                Utc.from_utc_datetime(&utc)
+                    .with_timezone(&Local)
+                    .naive_local()
+            }
+            // Times in other timezones are taken to be local, which is close
+            // enough for an hour-by-hour planner most of the time
+            None => NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+                .with_context(|| format!("invalid time '{value}' in {}", self.name))?,
+        };
+        Ok((time.date(), Some(time.time())))
+    }
+
+    fn timestamp(&self) -> anyhow::Result<DateTime<Local>> {
+        let (date, time) = self.time()?;
+        let time = date.and_time(time.unwrap_or_default());
+        Local
+            .from_local_datetime(&time)
+            .earliest()
+            .ok_or_else(|| anyhow!("{time} doesn't exist in the local timezone"))
+    }
+}
+
+/// Joins folded lines back together.
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// Reads the VTODOs and VEVENTs in a calendar. Things keeper can't represent,
+/// like repeating events, are left out with a warning.
+pub fn import(text: &str, now: DateTime<Local>) -> anyhow::Result<Vec<ImportedTask>> {
+    let mut tasks = Vec::new();
+    let mut component: Option<Vec<Property>> = None;
+    // How deep we are in components inside a task, like alarms
+    let mut nested = 0;
+    for (number, line) in unfold(text).iter().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let property =
+            Property::parse(line).with_context(|| format!("invalid line {}", number + 1))?;
+        let task = matches!(property.value.as_str(), "VTODO" | "VEVENT");
+        match (property.name.as_str(), &mut component) {
+            ("BEGIN", None) if task => component = Some(Vec::new()),
+            ("END", Some(_)) if task && nested == 0 => {
+                let properties = component.take().unwrap();
+                tasks.push(imported(&properties, now)?);
+            }
+            ("BEGIN", Some(_)) => nested += 1,
+            ("END", Some(_)) => nested -= 1,
+            (_, Some(properties)) if nested == 0 => properties.push(property),
+            _ => {}
+        }
+    }
+    Ok(tasks)
+}
+
+fn imported(properties: &[Property], now: DateTime<Local>) -> anyhow::Result<ImportedTask> {
+    let get = |name: &str| properties.iter().find(|p| p.name == name);
+
+    let desc = get("SUMMARY")
+        .map(|p| unescape(&p.value))
+        .filter(|desc| !desc.trim().is_empty())
+        .unwrap_or_else(|| "untitled".to_string());
+    let priority = match get("PRIORITY").and_then(|p| p.value.trim().parse().ok()) {
+        Some(1..=2) => Priority::Urgent,
+        Some(3..=4) => Priority::High,
+        Some(6..=9) => Priority::Low,
+        _ => Priority::Normal,
+    };
+    let mut task = Task::with_priority(desc, priority);
+
+    if let Some(uid) = get("UID") {
+        task.set_uid(unescape(&uid.value));
+    }
+    if let Some(created) = get("CREATED") {
+        task.touch(created.timestamp()?);
+    }
+    let done = get("STATUS").is_some_and(|p| p.value.eq_ignore_ascii_case("COMPLETED"));
+    match get("COMPLETED") {
+        Some(completed) => task.mark_complete(completed.timestamp()?),
+        None if done => task.mark_complete(now),
+        None => {}
+    }
+    if let Some(modified) = get("LAST-MODIFIED") {
+        task.touch(modified.timestamp()?);
+    }
+
+    let categories = properties.iter().filter(|p| p.name == "CATEGORIES");
+    for category in categories.flat_map(|p| split_escaped(&p.value, ',')) {
+        let category = category.trim();
+        match category.starts_with(['+', '@']) {
+            true => task.add_tag(category),
+            false if !category.is_empty() => task.add_tag(&format!("+{category}")),
+            false => {}
+        }
+    }
+    if let Some(description) = get("DESCRIPTION") {
+        task.set_details(Details::parse(&unescape(&description.value)));
+    }
+    if let Some(due) = get("DUE") {
+        let deadline = match due.time()? {
+            (date, None) => Deadline { date, hour: None },
+            // Due at 17:00 means done by the end of 16:00
+            (date, Some(time)) => {
+                let time = date.and_time(time) - Duration::seconds(1);
+                Deadline {
+                    date: time.date(),
+                    hour: Some(time.hour() as usize),
+                }
+            }
+        };
+        task.set_deadline(Some(deadline));
+    }
+    if get("RRULE").is_some() || get("RDATE").is_some() {
+        warn!(
+            "'{}' repeats, only its first occurrence was imported",
+            task.desc()
+        );
+    }
+
+    let slot = get("DTSTART")
+        .map(|start| start.time())
+        .transpose()?
+        .map(|(date, time)| (date, time.map_or(ALL_DAY_HOUR, |t| t.hour() as usize)));
+    let parent = get("RELATED-TO")
+        .filter(|p| {
+            p.param("RELTYPE")
+                .is_none_or(|r| r.eq_ignore_ascii_case("PARENT"))
+        })
+        .map(|p| unescape(&p.value));
+    Ok(ImportedTask { task, slot, parent })
+}
@@ -1,2 +1,19 @@
+pub mod checklist;
 pub mod cli;
+pub mod config;
+pub mod daemon;
 pub mod data;
+pub mod details;
+pub mod filter;
+pub mod font;
+pub mod ics;
+pub mod output;
+pub mod reminder;
+pub mod render;
+pub mod search;
+pub mod session;
+pub mod stats;
+pub mod statusbar;
+pub mod todotxt;
+pub mod tui;
+pub mod wallpaper;
@@ -1,28 +1,125 @@
-use std::{env, path::Path};
+use std::{
+    env, fs,
+    io::{self, Read},
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+};
 
-use anyhow::Context;
-use keeper_todo::{cli::Command, data::Keeper};
-use keeper_util::DataManager;
+use anyhow::{bail, Context};
+use chrono::Duration;
+use keeper_todo::{
+    checklist::{self, Flavor},
+    cli::{Command, FileFormat, ShowSet},
+    config::Config,
+    daemon,
+    data::{ColorStyle, Deadline, Keeper, Priority, Task},
+    filter::Filter,
+    font::Fonts,
+    ics,
+    output::{self, Output, StatsRecord},
+    reminder::{ReminderState, TaskKey},
+    session::{self, LineHelper, QUIT},
+    stats::{Stats, StatsReport},
+    statusbar::{self, BarState},
+    todotxt, tui,
+};
+use keeper_util::{clock::Clock, error, fatal, info, recover, DataManager};
+use rustyline::{error::ReadlineError, history::DefaultHistory, Editor};
 
 const DATA_PATH: &str = concat!(env!("HOME"), "/.local/share/keeper/data.ron");
+const CHART_SIZE: (u32, u32) = (1200, 600);
+/// Kept next to the data file, but not committed.
+const HISTORY_FILENAME: &str = "repl_history";
 
 fn main() -> anyhow::Result<()> {
     let dm = DataManager::<Keeper>::new(Path::new(DATA_PATH))?;
+    let clock = Clock::from_env();
     let mut keeper = dm.load_data()?;
+    keeper.set_clock(clock);
+    keeper.backfill_timestamps();
+    keeper.assign_ids();
 
-    let args = env::args();
-    let command = Command::parse(args);
+    let command = Command::parse(env::args(), clock);
     match command {
+        Command::Daemon => {
+            return daemon::run(&dm, clock).context("daemon failed");
+        }
+        Command::Status { bar, watch: true } => {
+            return statusbar::watch(&dm, clock, bar).context("status --watch failed");
+        }
+        Command::Status { bar, watch: false } => {
+            println!("{}", BarState::new(&keeper).format(bar));
+            // Bars run this every few seconds, so leave the data file alone
+            return Ok(());
+        }
+        Command::Tui => {
+            let changes = tui::run(&mut keeper).context("tui failed")?;
+            if changes.is_empty() {
+                return Ok(());
+            }
+            // One commit for the whole session, rather than one per key
+            keeper.order();
+            dm.commit_data(&keeper, &format!("Tui: {}", changes.join("; ")))?;
+            return keeper
+                .render(ShowSet::Date(clock.today()), &Filter::default())
+                .context("tui command failed");
+        }
+        Command::Batch { ref path } => return batch(&dm, keeper, path),
+        Command::Repl => return repl(&dm, keeper),
+        _ => run(&mut keeper, &dm, &command)?,
+    }
+
+    keeper.order();
+    dm.commit_data(&keeper, &format!("{command:?}"))?;
+
+    Ok(())
+}
+
+/// Runs one of the commands that can also run in a batch.
+fn run(keeper: &mut Keeper, dm: &DataManager<Keeper>, command: &Command) -> anyhow::Result<()> {
+    let clock = keeper.clock();
+    match *command {
         Command::Add {
             date,
             ref desc,
             hour,
+            priority,
+            ref tags,
+            deadline,
+            ref parent,
+        } => {
+            let task = new_task(desc, priority, tags, deadline);
+            match parent {
+                Some(path) => keeper.add_subtask(date, hour, path, task),
+                None => keeper.add(date, hour, task),
+            }
+            .context("add command failed")?;
+        }
+        Command::InboxAdd {
+            ref desc,
+            priority,
+            ref tags,
+            deadline,
         } => {
-            keeper.add(date, desc, hour).context("add command failed")?;
+            keeper
+                .inbox_add(new_task(desc, priority, tags, deadline))
+                .context("inbox add command failed")?;
+        }
+        Command::InboxList { ref filter } => {
+            keeper.inbox_list(filter);
         }
-        Command::Mark { date, hour, index } => {
+        Command::Schedule { index, date, hour } => {
+            keeper
+                .schedule(index, date, hour)
+                .context("schedule command failed")?;
+        }
+        Command::Mark {
+            date,
+            hour,
+            ref path,
+        } => {
             keeper
-                .mark(date, hour, index)
+                .mark(date, hour, path)
                 .context("mark command failed")?;
         }
         Command::Change {
@@ -35,16 +132,320 @@ fn main() -> anyhow::Result<()> {
                 .change(date, old_hour, index, new_hour)
                 .context("change command failed")?;
         }
-        Command::Show { set } => {
-            keeper.show(set);
+        Command::Delete {
+            date,
+            hour,
+            ref path,
+        } => {
+            keeper
+                .delete(date, hour, path)
+                .context("delete command failed")?;
+        }
+        Command::DeleteInbox { index } => {
+            keeper
+                .delete_inbox(index)
+                .context("delete command failed")?;
+        }
+        Command::Show {
+            set,
+            ref filter,
+            verbose,
+            format,
+        } => match format {
+            Some(format) => print!("{}", output::show(keeper, set, filter).format(format)?),
+            None => keeper.show(set, filter, verbose),
+        },
+        Command::Render { set, ref filter } => {
+            // Asked for, so not put off until the end of a batch
+            keeper
+                .render_now(set, filter)
+                .context("render command failed")?;
+        }
+        Command::List { ref filter } => {
+            keeper.list(filter);
+        }
+        Command::Search { ref search, format } => match format {
+            Some(format) => {
+                let hits = search.hits(keeper);
+                print!("{}", output::search(keeper, &hits).format(format)?);
+            }
+            None => keeper.search(search),
+        },
+        Command::History {
+            set,
+            ref filter,
+            format,
+        } => match format {
+            Some(format) => print!("{}", output::history(keeper, set, filter).format(format)?),
+            None => keeper.history(set, filter),
+        },
+        Command::Stats {
+            days,
+            period,
+            ref chart,
+            format,
+        } => {
+            let stats = Stats::new(keeper, days);
+            match format {
+                Some(format) => print!("{}", StatsRecord::new(&stats, period).format(format)?),
+                None => print!("{}", StatsReport::new(&stats, period, ColorStyle::Color)),
+            }
+            if let Some(path) = chart {
+                let config = Config::load()?;
+                let fonts = Fonts::load(&config.font).context("failed to load fonts")?;
+                stats
+                    .chart(period, &fonts, CHART_SIZE)
+                    .save(path)
+                    .with_context(|| format!("failed to save chart to {path:?}"))?;
+                // Don't get in the way of machine-readable output
+                if format.is_none() {
+                    info!("saved chart to {path:?}");
+                }
+            }
         }
-        Command::Render { set } => {
-            keeper.render(set).context("render command failed")?;
+        Command::Export {
+            format,
+            ref path,
+            from,
+            to,
+            events,
+        } => {
+            let text = match format {
+                FileFormat::Ics => ics::export(keeper, from, to, events),
+                FileFormat::TodoTxt => todotxt::export(keeper, from, to),
+                FileFormat::Markdown => checklist::export(keeper, Flavor::Markdown, from, to),
+                FileFormat::Org => checklist::export(keeper, Flavor::Org, from, to),
+            };
+            match path {
+                Some(path) => {
+                    fs::write(path, text).with_context(|| format!("failed to write {path:?}"))?;
+                    info!("exported to {path:?}");
+                }
+                None => print!("{text}"),
+            }
+        }
+        Command::Import {
+            format,
+            ref path,
+            date,
+        } => {
+            let text = read_input(path)?;
+            // Checklist items without a date heading go on this day
+            let day = date.unwrap_or(clock.today());
+            let tasks = match format {
+                FileFormat::Ics => ics::import(&text, clock.now()),
+                FileFormat::TodoTxt => todotxt::import(&text, clock.now()),
+                FileFormat::Markdown => {
+                    checklist::import(&text, Flavor::Markdown, day, clock.now())
+                }
+                FileFormat::Org => checklist::import(&text, Flavor::Org, day, clock.now()),
+            }
+            .with_context(|| format!("failed to import {path:?}"))?;
+            let (added, updated) = keeper.import(tasks);
+            info!("imported {added} new tasks and updated {updated}");
+            keeper
+                .render(ShowSet::Date(clock.today()), &Filter::default())
+                .context("import command failed")?;
+        }
+        Command::Notify => {
+            let config = Config::load()?;
+            let mut state = ReminderState::load(dm.data_dir())?;
+            state.notify(keeper, &config.reminders, clock.now());
+            state.save()?;
+        }
+        Command::Snooze {
+            date,
+            hour,
+            index,
+            minutes,
+        } => {
+            let Some(task) = keeper.task(date, hour, index) else {
+                fatal!("no task at {hour}.{index}");
+            };
+            let until = clock.now() + Duration::minutes(minutes.into());
+            let mut state = ReminderState::load(dm.data_dir())?;
+            state.snooze(TaskKey::of(task), until);
+            state.save()?;
+            info!("snoozed '{}' until {}", task.desc(), until.format("%H:%M"));
         }
+        Command::Remind {
+            date,
+            hour,
+            index,
+            minutes,
+        } => {
+            keeper.remind(date, hour, index, minutes);
+        }
+        Command::Depend {
+            date,
+            hour,
+            ref path,
+            on_date,
+            on_hour,
+            ref on_path,
+            remove,
+        } => {
+            keeper
+                .depend((date, hour, path), (on_date, on_hour, on_path), remove)
+                .context("depend command failed")?;
+        }
+        Command::Due {
+            date,
+            hour,
+            ref path,
+            deadline,
+        } => {
+            keeper
+                .due(date, hour, path, deadline)
+                .context("due command failed")?;
+        }
+        Command::Note {
+            date,
+            hour,
+            ref path,
+        } => {
+            keeper
+                .note(date, hour, path)
+                .context("note command failed")?;
+        }
+        Command::Status { bar, watch: false } => {
+            println!("{}", BarState::new(keeper).format(bar));
+        }
+        Command::Daemon
+        | Command::Status { watch: true, .. }
+        | Command::Tui
+        | Command::Batch { .. }
+        | Command::Repl => bail!("{command:?} only works on its own"),
     }
+    Ok(())
+}
+
+/// Runs each line of `path` as a command, then commits and renders once.
+/// Stops at the first line that fails, without saving anything.
+fn batch(dm: &DataManager<Keeper>, mut keeper: Keeper, path: &Path) -> anyhow::Result<()> {
+    let text = read_input(path)?;
+    keeper.defer_render(true);
+    let mut commands = Vec::new();
+    for (number, line) in text.lines().enumerate().map(|(i, line)| (i + 1, line)) {
+        match recover(|| run_line(&mut keeper, dm, line)) {
+            Some(Ok(Some(command))) => commands.push(command),
+            Some(Ok(None)) => {}
+            Some(Err(e)) => {
+                return Err(e).with_context(|| format!("line {number} failed, nothing was saved"))
+            }
+            None => fatal!("line {number} failed, nothing was saved"),
+        }
+    }
+    save(dm, keeper, "Batch", &commands)
+}
+
+/// Reads commands at a prompt until `quit` or Ctrl-D, then saves them like
+/// [`batch`]. A command that fails is reported and the rest carry on.
+fn repl(dm: &DataManager<Keeper>, mut keeper: Keeper) -> anyhow::Result<()> {
+    let history = dm.data_dir().join(HISTORY_FILENAME);
+    let mut editor =
+        Editor::<LineHelper, DefaultHistory>::new().context("failed to start the prompt")?;
+    editor.set_helper(Some(LineHelper::new(&keeper)));
+    // Fine on the first run, when there's no history yet
+    let _ = editor.load_history(&history);
+
+    keeper.defer_render(true);
+    let mut commands = Vec::new();
+    loop {
+        let line = match editor.readline("keeper> ") {
+            Ok(line) => line,
+            // Only clears the line, like in a shell
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => {
+                error!("failed to read line: {e}");
+                break;
+            }
+        };
+        if QUIT.contains(&line.trim()) {
+            break;
+        }
+        editor
+            .add_history_entry(line.as_str())
+            .context("failed to add to history")?;
+        // A bug in one command shouldn't lose everything else from the session
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            recover(|| run_line(&mut keeper, dm, &line))
+        }));
+        match result {
+            Ok(Some(Ok(Some(command)))) => commands.push(command),
+            Ok(Some(Ok(None)) | None) => {}
+            Ok(Some(Err(e))) => error!("{e:#}"),
+            // The panic message has already been printed
+            Err(_) => error!("[{}] crashed, carrying on", line.trim()),
+        }
+        // Pick up new tags
+        editor.set_helper(Some(LineHelper::new(&keeper)));
+    }
+    editor
+        .save_history(&history)
+        .with_context(|| format!("failed to save history to {history:?}"))?;
+    save(dm, keeper, "Repl", &commands)
+}
 
+/// Runs the command on `line`, returning it if it changed anything that needs
+/// saving.
+fn run_line(
+    keeper: &mut Keeper,
+    dm: &DataManager<Keeper>,
+    line: &str,
+) -> anyhow::Result<Option<Command>> {
+    let command = session::parse_line(line, keeper.clock())?;
+    if let Some(command) = &command {
+        run(keeper, dm, command)?;
+    }
+    Ok(command.filter(Command::changes_data))
+}
+
+/// Commits what `commands` did in one go and renders the wallpaper once.
+fn save(
+    dm: &DataManager<Keeper>,
+    mut keeper: Keeper,
+    name: &str,
+    commands: &[Command],
+) -> anyhow::Result<()> {
+    if commands.is_empty() {
+        return Ok(());
+    }
     keeper.order();
-    dm.commit_data(&keeper, &format!("{command:?}"))?;
+    let lines: Vec<_> = commands
+        .iter()
+        .map(|command| format!("{command:?}"))
+        .collect();
+    let message = match lines.len() {
+        1 => format!("{name}: {}", lines[0]),
+        count => format!("{name}: {count} commands\n\n{}", lines.join("\n")),
+    };
+    dm.commit_data(&keeper, &message)?;
+    keeper
+        .render_now(ShowSet::Date(keeper.clock().today()), &Filter::default())
+        .with_context(|| format!("{} command failed", name.to_lowercase()))
+}
 
-    Ok(())
+/// Reads all of `path`, or stdin for `-`.
+fn read_input(path: &Path) -> anyhow::Result<String> {
+    let mut text = String::new();
+    match path.to_str() {
+        Some("-") => io::stdin()
+            .read_to_string(&mut text)
+            .context("failed to read stdin")?,
+        _ => fs::File::open(path)
+            .and_then(|mut file| file.read_to_string(&mut text))
+            .with_context(|| format!("failed to read {path:?}"))?,
+    };
+    Ok(text)
+}
+
+fn new_task(desc: &str, priority: Priority, tags: &[String], deadline: Option<Deadline>) -> Task {
+    let mut task = Task::with_priority(desc.to_string(), priority);
+    for tag in tags {
+        task.add_tag(tag);
+    }
+    task.set_deadline(deadline);
+    task
 }
@@ -0,0 +1,361 @@
+//! Machine-readable output for `show`, `search`, `history` and `stats`, so
+//! scripts don't have to scrape the colored text. The fields are documented
+//! in the README, and only ever get added to.
+
+use anyhow::Context;
+use chrono::{DateTime, Days, Local, NaiveDate, SecondsFormat};
+use serde::Serialize;
+
+use crate::{
+    cli::ShowSet,
+    data::{fmt_id, past_due, Keeper, Task, TaskIndex, TaskStatus},
+    filter::Filter,
+    search::{walk, Hit},
+    stats::{percent, Period, Stats},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Json,
+    Ron,
+    Csv,
+}
+
+impl OutputFormat {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "ron" => Some(Self::Ron),
+            "csv" => Some(Self::Csv),
+            _ => None,
+        }
+    }
+}
+
+/// Something that can be written in any [`OutputFormat`].
+pub trait Output: Serialize {
+    /// The header, then a row for each record, for CSV.
+    fn rows(&self) -> Vec<Vec<String>>;
+
+    fn format(&self, format: OutputFormat) -> anyhow::Result<String> {
+        let mut out = match format {
+            OutputFormat::Json => {
+                serde_json::to_string_pretty(self).context("failed to serialize JSON")?
+            }
+            OutputFormat::Ron => ron::ser::to_string_pretty(self, Default::default())
+                .context("failed to serialize RON")?,
+            OutputFormat::Csv => return Ok(csv(&self.rows())),
+        };
+        out.push('\n');
+        Ok(out)
+    }
+}
+
+fn csv(rows: &[Vec<String>]) -> String {
+    let mut out = String::new();
+    for row in rows {
+        let fields: Vec<_> = row
+            .iter()
+            .map(|field| match field.contains([',', '"', '\n', '\r']) {
+                true => format!("\"{}\"", field.replace('"', "\"\"")),
+                false => field.clone(),
+            })
+            .collect();
+        out.push_str(&fields.join(","));
+        out.push_str("\r\n");
+    }
+    out
+}
+
+fn optional(value: Option<impl ToString>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+/// A task, with what's needed to refer to it in other commands.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TaskRecord {
+    /// The day it's planned for, or `None` in the inbox.
+    pub date: Option<NaiveDate>,
+    pub hour: Option<usize>,
+    /// The id other commands take, like `14.0.2`, or the index in the inbox.
+    pub id: String,
+    /// Stays the same as the task moves around, unlike `id`.
+    pub uid: String,
+    pub desc: String,
+    pub priority: String,
+    pub tags: Vec<String>,
+    pub status: TaskStatus,
+    /// Waiting on another task.
+    pub blocked: bool,
+    pub due: Option<NaiveDate>,
+    pub due_hour: Option<usize>,
+    pub created: Option<DateTime<Local>>,
+    pub modified: Option<DateTime<Local>>,
+    pub completed_at: Option<DateTime<Local>>,
+}
+
+impl TaskRecord {
+    /// `path` is the task's index in its hour (or the inbox), then subtask
+    /// indices.
+    pub fn new(
+        keeper: &Keeper,
+        by_id: &TaskIndex,
+        slot: Option<(NaiveDate, usize)>,
+        path: &[usize],
+        task: &Task,
+    ) -> Self {
+        let now = keeper.clock().now();
+        let overdue = match slot {
+            Some((date, hour)) => task.overdue(past_due(date, hour, now), now),
+            None => task.overdue(false, now),
+        };
+        let deadline = task.deadline();
+        Self {
+            date: slot.map(|(date, _)| date),
+            hour: slot.map(|(_, hour)| hour),
+            id: match slot {
+                Some((_, hour)) => fmt_id(hour, path),
+                None => path[0].to_string(),
+            },
+            uid: task.uid(),
+            desc: task.desc().to_string(),
+            priority: format!("{:?}", task.priority()).to_lowercase(),
+            tags: task.tags().to_vec(),
+            status: TaskStatus::of(task, overdue),
+            blocked: by_id.blocked(task),
+            due: deadline.map(|deadline| deadline.date),
+            due_hour: deadline.and_then(|deadline| deadline.hour),
+            created: task.created(),
+            modified: task.modified(),
+            completed_at: task.completed_at().filter(|_| task.completed()),
+        }
+    }
+}
+
+impl Output for Vec<TaskRecord> {
+    fn rows(&self) -> Vec<Vec<String>> {
+        let header = [
+            "date",
+            "hour",
+            "id",
+            "uid",
+            "desc",
+            "priority",
+            "tags",
+            "status",
+            "blocked",
+            "due",
+            "due_hour",
+            "created",
+            "modified",
+            "completed_at",
+        ];
+        let mut rows = vec![header.map(String::from).to_vec()];
+        for record in self {
+            // The same as serde writes them
+            let time = |at: Option<DateTime<Local>>| {
+                optional(at.map(|at| at.to_rfc3339_opts(SecondsFormat::AutoSi, true)))
+            };
+            rows.push(vec![
+                optional(record.date),
+                optional(record.hour),
+                record.id.clone(),
+                record.uid.clone(),
+                record.desc.clone(),
+                record.priority.clone(),
+                record.tags.join(" "),
+                format!("{:?}", record.status).to_lowercase(),
+                record.blocked.to_string(),
+                optional(record.due),
+                optional(record.due_hour),
+                time(record.created),
+                time(record.modified),
+                time(record.completed_at),
+            ]);
+        }
+        rows
+    }
+}
+
+/// The tasks `show` shows, subtasks right after their parents.
+pub fn show(keeper: &Keeper, set: ShowSet, filter: &Filter) -> Vec<TaskRecord> {
+    let dates: Vec<_> = match set {
+        ShowSet::Days(days) => keeper.clock().today().iter_days().take(days).collect(),
+        ShowSet::Date(date) => vec![date],
+    };
+    let by_id = keeper.index();
+    let mut records = Vec::new();
+    for date in dates {
+        let Some(schedule) = keeper.days.get(&date) else {
+            continue;
+        };
+        for (hour, tasks) in &schedule.timeslots {
+            for (index, task) in tasks.iter().enumerate() {
+                if !filter.matches(task) {
+                    continue;
+                }
+                walk(task, &mut vec![index], &mut |path, task| {
+                    records.push(TaskRecord::new(
+                        keeper,
+                        &by_id,
+                        Some((date, *hour)),
+                        path,
+                        task,
+                    ))
+                });
+            }
+        }
+    }
+    records
+}
+
+pub fn search(keeper: &Keeper, hits: &[Hit]) -> Vec<TaskRecord> {
+    let by_id = keeper.index();
+    hits.iter()
+        .map(|hit| TaskRecord::new(keeper, &by_id, hit.slot, &hit.path, hit.task))
+        .collect()
+}
+
+/// The tasks `history` shows, in the order they got done.
+pub fn history(keeper: &Keeper, set: ShowSet, filter: &Filter) -> Vec<TaskRecord> {
+    let today = keeper.clock().today();
+    let dates: Vec<_> = match set {
+        ShowSet::Days(days) => (0..days as u64).map(|ago| today - Days::new(ago)).collect(),
+        ShowSet::Date(date) => vec![date],
+    };
+    let by_id = keeper.index();
+    let mut records = Vec::new();
+    let mut check = |slot, path: &[usize], task: &Task| {
+        let Some(at) = task.completed_at().filter(|_| task.completed()) else {
+            return;
+        };
+        if !dates.contains(&at.date_naive()) || !filter.matches(task) {
+            return;
+        }
+        records.push(TaskRecord::new(keeper, &by_id, slot, path, task));
+    };
+    for (date, schedule) in &keeper.days {
+        for (hour, tasks) in &schedule.timeslots {
+            for (index, task) in tasks.iter().enumerate() {
+                walk(task, &mut vec![index], &mut |path, task| {
+                    check(Some((*date, *hour)), path, task)
+                });
+            }
+        }
+    }
+    for (index, task) in keeper.inbox.iter().enumerate() {
+        walk(task, &mut vec![index], &mut |path, task| {
+            check(None, path, task)
+        });
+    }
+    records.sort_by_key(|record| record.completed_at);
+    records
+}
+
+/// Everything `stats` reports.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct StatsRecord {
+    pub period: String,
+    pub periods: Vec<PeriodRecord>,
+    pub days: Vec<DayRecord>,
+    pub average_overdue: f32,
+    pub streak: usize,
+    pub longest_streak: usize,
+    pub busiest_hours: Vec<HourRecord>,
+    pub deferred: Vec<DeferredRecord>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct PeriodRecord {
+    /// The first day of the period.
+    pub start: NaiveDate,
+    pub done: usize,
+    pub total: usize,
+    /// The percentage done, rounded.
+    pub rate: usize,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DayRecord {
+    pub date: NaiveDate,
+    pub done: usize,
+    pub total: usize,
+    pub overdue: usize,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct HourRecord {
+    pub hour: usize,
+    pub tasks: usize,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DeferredRecord {
+    pub uid: String,
+    pub desc: String,
+    pub deferred: u32,
+}
+
+impl StatsRecord {
+    pub fn new(stats: &Stats, period: Period) -> Self {
+        let (streak, longest_streak) = stats.streaks();
+        Self {
+            period: period.name().to_string(),
+            periods: stats
+                .periods(period)
+                .into_iter()
+                .map(|(start, done, total)| PeriodRecord {
+                    start,
+                    done,
+                    total,
+                    rate: percent(done, total),
+                })
+                .collect(),
+            days: stats
+                .days
+                .iter()
+                .map(|day| DayRecord {
+                    date: day.date,
+                    done: day.done,
+                    total: day.total,
+                    overdue: day.overdue,
+                })
+                .collect(),
+            average_overdue: stats.average_overdue(),
+            streak,
+            longest_streak,
+            busiest_hours: stats
+                .busiest_hours()
+                .into_iter()
+                .map(|(hour, tasks)| HourRecord { hour, tasks })
+                .collect(),
+            deferred: stats
+                .deferred
+                .iter()
+                .map(|task| DeferredRecord {
+                    uid: task.uid(),
+                    desc: task.desc().to_string(),
+                    deferred: task.deferred(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// As CSV, just the completion rate for each period.
+impl Output for StatsRecord {
+    fn rows(&self) -> Vec<Vec<String>> {
+        let mut rows = vec![["start", "done", "total", "rate"]
+            .map(String::from)
+            .to_vec()];
+        for period in &self.periods {
+            rows.push(vec![
+                period.start.to_string(),
+                period.done.to_string(),
+                period.total.to_string(),
+                period.rate.to_string(),
+            ]);
+        }
+        rows
+    }
+}
@@ -0,0 +1,243 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+use anyhow::{ensure, Context};
+use chrono::{DateTime, Days, Duration, Local, NaiveDate, TimeZone};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::ReminderConfig,
+    data::{Keeper, Task},
+};
+use keeper_util::{error, info, warn};
+
+/// Lives next to the data file, but isn't committed: it changes far too often
+/// and is useless without the data anyways.
+const STATE_FILE: &str = "reminders.ron";
+/// Reminders for days older than this are forgotten.
+const KEEP_DAYS: u64 = 7;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ReminderKind {
+    /// The task's hour is about to start.
+    Upcoming,
+    /// The task's deadline, or its hour if it has none, has passed and it
+    /// isn't complete.
+    Overdue,
+}
+
+/// Identifies a task across runs, so editing or moving it doesn't send its
+/// reminders again. Tasks without an id yet fall back to their uid.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TaskKey {
+    Id(u64),
+    Uid(String),
+}
+
+impl TaskKey {
+    pub fn of(task: &Task) -> Self {
+        match task.id() {
+            0 => Self::Uid(task.uid()),
+            id => Self::Id(id),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Reminder {
+    key: TaskKey,
+    kind: ReminderKind,
+    date: NaiveDate,
+    hour: usize,
+    desc: String,
+    at: DateTime<Local>,
+}
+
+/// Which reminders have already gone out, and which tasks are snoozed.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct ReminderState {
+    #[serde(skip)]
+    path: PathBuf,
+    /// The day each reminder was for, so old ones can be forgotten.
+    sent: BTreeMap<(TaskKey, ReminderKind), NaiveDate>,
+    snoozed: BTreeMap<TaskKey, DateTime<Local>>,
+}
+
+impl ReminderState {
+    pub fn load(data_dir: &Path) -> anyhow::Result<Self> {
+        let path = data_dir.join(STATE_FILE);
+        let mut state = if path.exists() {
+            let contents = fs::read_to_string(&path)
+                .with_context(|| format!("failed to read reminder state from {path:?}"))?;
+            // Worst case a reminder goes out twice, so an old or broken file
+            // isn't worth failing over
+            ron::from_str(&contents).unwrap_or_else(|e| {
+                warn!("ignoring reminder state at {path:?}: {e}");
+                Self::default()
+            })
+        } else {
+            Self::default()
+        };
+        state.path = path;
+        Ok(state)
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let ron = ron::ser::to_string_pretty(self, Default::default())
+            .context("failed to serialize RON")?;
+        fs::write(&self.path, ron)
+            .with_context(|| format!("failed to write reminder state to {:?}", self.path))
+    }
+
+    /// Holds off on reminders for `key` until `until`. Reminders that already
+    /// went out will go out again once the snooze is over.
+    pub fn snooze(&mut self, key: TaskKey, until: DateTime<Local>) {
+        self.sent.retain(|(sent, _), _| *sent != key);
+        self.snoozed.insert(key, until);
+    }
+
+    /// When the next reminder that hasn't gone out yet is due.
+    pub fn next(
+        &self,
+        keeper: &Keeper,
+        config: &ReminderConfig,
+        now: DateTime<Local>,
+    ) -> Option<DateTime<Local>> {
+        reminders(keeper, config, now)
+            .into_iter()
+            .filter(|r| !self.sent.contains_key(&(r.key.clone(), r.kind)))
+            .map(|r| self.fire_at(&r))
+            .filter(|at| *at > now)
+            .min()
+    }
+
+    /// Sends every reminder that is due and hasn't gone out yet.
+    pub fn notify(&mut self, keeper: &Keeper, config: &ReminderConfig, now: DateTime<Local>) {
+        for reminder in reminders(keeper, config, now) {
+            let sent = (reminder.key.clone(), reminder.kind);
+            if self.sent.contains_key(&sent) || self.fire_at(&reminder) > now {
+                continue;
+            }
+
+            match send(&reminder) {
+                Ok(()) => {
+                    info!("sent reminder for '{}'", reminder.desc);
+                    self.sent.insert(sent, reminder.date);
+                }
+                Err(e) => error!("failed to send reminder: {e:#}"),
+            }
+        }
+
+        // Forget about old days so the state file doesn't grow forever
+        let cutoff = now.date_naive() - Days::new(KEEP_DAYS);
+        self.sent.retain(|_, date| *date >= cutoff);
+        self.snoozed.retain(|_, until| until.date_naive() >= cutoff);
+    }
+
+    fn fire_at(&self, reminder: &Reminder) -> DateTime<Local> {
+        match self.snoozed.get(&reminder.key) {
+            Some(until) => reminder.at.max(*until),
+            None => reminder.at,
+        }
+    }
+}
+
+/// Start of the given hour (24 being midnight of the next day), resolving DST
+/// gaps and overlaps to the earliest sensible time.
+fn hour_start(date: NaiveDate, hour: usize) -> Option<DateTime<Local>> {
+    let naive = date.and_hms_opt(0, 0, 0)? + Duration::hours(hour as i64);
+    Local.from_local_datetime(&naive).earliest().or_else(|| {
+        Local
+            .from_local_datetime(&(naive + Duration::hours(1)))
+            .earliest()
+    })
+}
+
+/// All reminders for incomplete tasks around `now`. Only yesterday, today and
+/// tomorrow are considered, so that a fresh state file doesn't unleash a flood
+/// of overdue reminders for ancient tasks.
+fn reminders(keeper: &Keeper, config: &ReminderConfig, now: DateTime<Local>) -> Vec<Reminder> {
+    let today = now.date_naive();
+    let mut reminders = Vec::new();
+    for (date, schedule) in keeper
+        .days
+        .range(today - Days::new(1)..=today + Days::new(1))
+    {
+        for (hour, tasks) in schedule.timeslots.iter() {
+            let (Some(start), Some(end)) = (hour_start(*date, *hour), hour_start(*date, hour + 1))
+            else {
+                continue;
+            };
+
+            for task in tasks.iter().filter(|task| !task.completed()) {
+                let key = TaskKey::of(task);
+                let lead = task.remind().unwrap_or(config.lead_minutes);
+                // There's no point saying something is coming up once it's over
+                if now < end {
+                    reminders.push(Reminder {
+                        key: key.clone(),
+                        kind: ReminderKind::Upcoming,
+                        date: *date,
+                        hour: *hour,
+                        desc: task.desc().to_string(),
+                        at: start - Duration::minutes(lead.into()),
+                    });
+                }
+                if config.overdue {
+                    // A deadline decides when it's late, not the hour it's planned for
+                    let due = task
+                        .deadline()
+                        .and_then(|deadline| hour_start(deadline.date, deadline.hour() + 1));
+                    reminders.push(Reminder {
+                        key,
+                        kind: ReminderKind::Overdue,
+                        date: *date,
+                        hour: *hour,
+                        desc: task.desc().to_string(),
+                        at: due.unwrap_or(end),
+                    });
+                }
+            }
+        }
+    }
+    reminders
+}
+
+fn send(reminder: &Reminder) -> anyhow::Result<()> {
+    let Reminder { hour, desc, .. } = reminder;
+    let (summary, urgency) = match reminder.kind {
+        ReminderKind::Upcoming => (format!("Coming up at {hour}:00"), "normal"),
+        ReminderKind::Overdue => (
+            format!("Overdue since {}", reminder.at.format("%H:%M on %d %b")),
+            "critical",
+        ),
+    };
+
+    let status = if cfg!(target_os = "macos") {
+        let script = format!(
+            "display notification {} with title {}",
+            applescript_string(desc),
+            applescript_string(&summary)
+        );
+        process::Command::new("osascript")
+            .args(["-e", &script])
+            .status()
+            .context("failed to run osascript")?
+    } else {
+        process::Command::new("notify-send")
+            .args(["--app-name=keeper", "--urgency", urgency, &summary, desc])
+            .status()
+            .context("failed to run notify-send")?
+    };
+    ensure!(status.success(), "notification command failed");
+    Ok(())
+}
+
+/// Quotes `s` for AppleScript, which only knows the `\\` and `\"` escapes.
+fn applescript_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
@@ -0,0 +1,707 @@
+use std::{collections::BTreeMap, path::Path};
+
+use anyhow::Context;
+use chrono::{DateTime, Local, NaiveDate};
+use image::{ImageBuffer, Rgb, RgbImage};
+use imageproc::{
+    drawing::draw_filled_rect_mut, drawing::draw_text_mut, pixelops::weighted_sum, rect::Rect,
+};
+use rusttype::{point, Font, Scale};
+
+use crate::{
+    cli::ShowSet,
+    data::{past_due, Keeper, Priority, Schedule, Task, TaskIndex},
+    filter::Filter,
+    font::{Face, Fonts},
+};
+
+const Y_START: u32 = 35;
+const Y_PAD: u32 = 20;
+const X_PAD: u32 = 35;
+/// Anything smaller has no room to draw in inside the padding.
+pub const MIN_SIZE: (u32, u32) = (2 * X_PAD + 20 + 1, 2 * Y_PAD + Y_START + 1);
+/// Below this size (in pixels) text stops being readable from across the desk,
+/// so we truncate instead of shrinking any further.
+const MIN_FONT_SIZE: f32 = 18.0;
+/// Lines longer than this many characters get wrapped instead of shrinking the
+/// whole wallpaper to fit them.
+const MAX_COLUMN_CHARS: usize = 80;
+/// At the minimum font size, a single line never wraps onto more rows than this.
+const MAX_WRAPPED_ROWS: usize = 3;
+const ELLIPSIS: &str = "…";
+/// Marks tasks with a note or links, if the fonts can draw it.
+const ATTACHMENT: char = '📎';
+pub const NORD_BG: Rgb<u8> = Rgb([0x2e, 0x34, 0x40]);
+pub(crate) const NORD_GREEN: Rgb<u8> = Rgb([0xa3, 0xbe, 0x8c]);
+const NORD_RED: Rgb<u8> = Rgb([0xbf, 0x61, 0x6a]);
+pub(crate) const NORD_WHITE: Rgb<u8> = Rgb([0xd8, 0xde, 0xe9]);
+const NORD_BLUE: Rgb<u8> = Rgb([0x81, 0xa1, 0xc1]);
+const NORD_YELLOW: Rgb<u8> = Rgb([0xeb, 0xcb, 0x8b]);
+pub(crate) const NORD_GREY: Rgb<u8> = Rgb([0x4c, 0x56, 0x6a]);
+const NORD_ORANGE: Rgb<u8> = Rgb([0xd0, 0x87, 0x70]);
+const NORD_CYAN: Rgb<u8> = Rgb([0x88, 0xc0, 0xd0]);
+const NORD_PURPLE: Rgb<u8> = Rgb([0xb4, 0x8e, 0xad]);
+/// Space between the text of a line and its decoration, in multiples of the
+/// font size.
+const DECORATION_GAP: f32 = 0.5;
+/// How much thicker fake bold is, in multiples of the font size.
+const FAKE_BOLD: f32 = 0.04;
+/// How far fake italics lean, in pixels per pixel above the baseline.
+const FAKE_SLANT: f32 = 0.2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct Style {
+    face: Face,
+    strikethrough: bool,
+}
+
+const BOLD: Style = Style {
+    face: Face::Bold,
+    strikethrough: false,
+};
+const DONE: Style = Style {
+    face: Face::Italic,
+    strikethrough: true,
+};
+
+/// A run of text drawn in a single color and style.
+struct Span {
+    color: Rgb<u8>,
+    style: Style,
+    text: String,
+}
+
+/// Something drawn in the space to the right of a line's text.
+#[derive(Debug, Clone, Copy)]
+enum Decoration {
+    /// A horizontal rule, e.g. for the "now" marker.
+    Rule(Rgb<u8>),
+    /// A bar filled up to the given fraction.
+    Progress(f32),
+}
+
+/// A logical line of output, before wrapping.
+#[derive(Default)]
+struct Line {
+    spans: Vec<Span>,
+    decoration: Option<Decoration>,
+}
+
+impl Line {
+    fn push(&mut self, color: Rgb<u8>, text: &str) {
+        self.push_styled(color, Style::default(), text);
+    }
+
+    fn push_styled(&mut self, color: Rgb<u8>, style: Style, text: &str) {
+        match self.spans.last_mut() {
+            Some(span) if span.color == color && span.style == style => span.text.push_str(text),
+            _ => self.spans.push(Span {
+                color,
+                style,
+                text: text.to_string(),
+            }),
+        }
+    }
+
+    fn advance(&self, fonts: &Fonts, scale: Scale) -> f32 {
+        self.spans
+            .iter()
+            .map(|span| fonts.advance(span.style.face, scale, &span.text))
+            .sum()
+    }
+}
+
+/// A line as it will actually be drawn, after wrapping.
+#[derive(Default)]
+struct Row {
+    indent: f32,
+    line: Line,
+}
+
+/// Breaks `line` into rows no wider than `width`. Breaks happen at spaces when
+/// possible, and anywhere inside a word that is too long for a row on its own.
+/// Continuation rows are indented to hang under the text after the first span
+/// (i.e. after the `[hour]`).
+fn wrap(fonts: &Fonts, scale: Scale, line: &Line, width: f32) -> Vec<Row> {
+    let indent = line
+        .spans
+        .first()
+        .map(|span| {
+            fonts.advance(span.style.face, scale, &span.text)
+                + fonts.advance(Face::Regular, scale, " ")
+        })
+        .unwrap_or(0.0)
+        .min(width / 2.0);
+
+    // Words can span several spans (e.g. "(desc)"), so only break at spaces
+    let mut words: Vec<Vec<(&Span, &str)>> = vec![Vec::new()];
+    for span in &line.spans {
+        for piece in span.text.split_inclusive(' ') {
+            words.last_mut().unwrap().push((span, piece));
+            if piece.ends_with(' ') {
+                words.push(Vec::new());
+            }
+        }
+    }
+
+    let mut rows = vec![Row::default()];
+    let mut x = 0.0;
+    for word in words {
+        let visible: f32 = word
+            .iter()
+            .map(|(span, piece)| fonts.advance(span.style.face, scale, piece.trim_end()))
+            .sum();
+        let row = rows.last().expect("there is always a row");
+        if x + visible > width && !row.line.spans.is_empty() {
+            rows.push(Row {
+                indent,
+                line: Line::default(),
+            });
+            x = indent;
+        }
+
+        if x + visible <= width {
+            for (span, piece) in word {
+                rows.last_mut()
+                    .unwrap()
+                    .line
+                    .push_styled(span.color, span.style, piece);
+                x += fonts.advance(span.style.face, scale, piece);
+            }
+            continue;
+        }
+
+        // The word doesn't fit on a row of its own, so split it up
+        for (span, piece) in word {
+            for c in piece.chars() {
+                let c = c.to_string();
+                let c_width = fonts.advance(span.style.face, scale, &c);
+                if x + c_width > width && x > indent {
+                    rows.push(Row {
+                        indent,
+                        line: Line::default(),
+                    });
+                    x = indent;
+                }
+                rows.last_mut()
+                    .unwrap()
+                    .line
+                    .push_styled(span.color, span.style, &c);
+                x += c_width;
+            }
+        }
+    }
+    rows.last_mut().unwrap().line.decoration = line.decoration;
+    rows
+}
+
+/// Cuts `rows` down to at most `max_rows`, marking the cut with an ellipsis.
+fn truncate(fonts: &Fonts, scale: Scale, rows: &mut Vec<Row>, max_rows: usize, width: f32) {
+    if rows.len() <= max_rows {
+        return;
+    }
+    rows.truncate(max_rows);
+
+    let last = rows.last_mut().expect("max_rows is nonzero");
+    let ellipsis_width = fonts.advance(Face::Regular, scale, ELLIPSIS);
+    while last.indent + last.line.advance(fonts, scale) + ellipsis_width > width {
+        let Some(span) = last.line.spans.last_mut() else {
+            break;
+        };
+        span.text.pop();
+        if span.text.is_empty() {
+            last.line.spans.pop();
+        }
+    }
+    let color = last.line.spans.last().map_or(NORD_WHITE, |span| span.color);
+    last.line.push(color, ELLIPSIS);
+}
+
+/// Picks a font size and wraps `lines` so they fit in a `width` by `height`
+/// box. Text is made as large as possible without going past
+/// [`MAX_COLUMN_CHARS`] columns; longer lines get wrapped. If even
+/// [`MIN_FONT_SIZE`] is too big, long lines are cut off with an ellipsis and
+/// lines that don't fit at all are summarized by a "+N more" marker.
+fn layout(fonts: &Fonts, lines: &[Line], width: f32, height: f32) -> (Scale, Vec<Row>) {
+    let unit = Scale::uniform(1.0);
+    let widest = lines
+        .iter()
+        .map(|line| line.advance(fonts, unit))
+        .fold(0.0, f32::max);
+    let column = fonts.advance(Face::Regular, unit, &"0".repeat(MAX_COLUMN_CHARS));
+    let mut size = (width / widest.min(column).max(f32::EPSILON))
+        .min(height / (lines.len() as f32 * fonts.line_height(unit)));
+
+    while size > MIN_FONT_SIZE {
+        let scale = Scale::uniform(size);
+        let rows: Vec<_> = lines
+            .iter()
+            .flat_map(|line| wrap(fonts, scale, line, width))
+            .collect();
+        if rows.len() as f32 * fonts.line_height(scale) <= height {
+            return (scale, rows);
+        }
+        size *= 0.95;
+    }
+
+    let scale = Scale::uniform(MIN_FONT_SIZE);
+    let fit = ((height / fonts.line_height(scale)) as usize).max(1);
+    let mut wrapped: Vec<_> = lines
+        .iter()
+        .map(|line| {
+            let mut rows = wrap(fonts, scale, line, width);
+            truncate(fonts, scale, &mut rows, MAX_WRAPPED_ROWS, width);
+            rows
+        })
+        .collect();
+
+    if wrapped.iter().map(Vec::len).sum::<usize>() <= fit {
+        return (scale, wrapped.into_iter().flatten().collect());
+    }
+
+    // Leave one row for the overflow marker
+    let mut rows = Vec::new();
+    let mut shown = 0;
+    for line in &mut wrapped {
+        if rows.len() + line.len() >= fit {
+            break;
+        }
+        rows.append(line);
+        shown += 1;
+    }
+    let mut marker = Line::default();
+    marker.push(NORD_WHITE, &format!("+{} more", lines.len() - shown));
+    rows.push(Row {
+        indent: 0.0,
+        line: marker,
+    });
+    (scale, rows)
+}
+
+pub struct KeeperRenderer<'a> {
+    keeper: &'a Keeper,
+    by_id: TaskIndex<'a>,
+    selection: ShowSet,
+    filter: Filter,
+    tag_colors: BTreeMap<String, Rgb<u8>>,
+    show_inbox: bool,
+    image: ImageBuffer<Rgb<u8>, Vec<u8>>,
+    fonts: &'a Fonts,
+    lines: Vec<Line>,
+}
+
+impl<'a> KeeperRenderer<'a> {
+    pub fn new(
+        keeper: &'a Keeper,
+        selection: ShowSet,
+        filter: Filter,
+        background_color: Rgb<u8>,
+        fonts: &'a Fonts,
+        (width, height): (u32, u32),
+    ) -> Self {
+        let image = ImageBuffer::from_pixel(width, height, background_color);
+
+        Self {
+            keeper,
+            by_id: keeper.index(),
+            selection,
+            filter,
+            tag_colors: BTreeMap::new(),
+            show_inbox: false,
+            image,
+            fonts,
+            lines: Vec::new(),
+        }
+    }
+
+    /// Colors for specific tags, keyed by lowercase tag. Other tags are cyan.
+    pub fn set_tag_colors(&mut self, tag_colors: BTreeMap<String, Rgb<u8>>) {
+        self.tag_colors = tag_colors;
+    }
+
+    /// Whether to list the inbox after the days.
+    pub fn set_show_inbox(&mut self, show_inbox: bool) {
+        self.show_inbox = show_inbox;
+    }
+
+    fn newline(&mut self) -> &mut Line {
+        self.lines.push(Line::default());
+        self.lines.last_mut().unwrap()
+    }
+
+    fn render_day(&mut self, day: NaiveDate) {
+        let now = self.keeper.clock().now();
+        let past_due = |time: usize| past_due(day, time, now);
+
+        // Date header
+        let mut header = Line::default();
+        header.push_styled(NORD_WHITE, BOLD, &format!("{}", day.format("%d %b %Y")));
+
+        let keeper = self.keeper;
+        let Some(Schedule { timeslots }) = keeper.days.get(&day) else {
+            self.lines.push(header);
+            self.newline().push(NORD_WHITE, "Empty");
+            return;
+        };
+        let timeslots: Vec<_> = timeslots
+            .iter()
+            .map(|(time, tasks)| {
+                let tasks: Vec<_> = tasks.iter().filter(|t| self.filter.matches(t)).collect();
+                (*time, tasks)
+            })
+            .filter(|(_, tasks)| !tasks.is_empty())
+            .collect();
+        if timeslots.is_empty() {
+            self.lines.push(header);
+            self.newline().push(NORD_WHITE, "Empty");
+            return;
+        }
+
+        let tasks = || {
+            timeslots
+                .iter()
+                .flat_map(|(time, tasks)| tasks.iter().map(|t| (*time, *t)))
+        };
+        let total = tasks().count();
+        let done = tasks().filter(|(_, task)| task.completed()).count();
+        let overdue = tasks()
+            .filter(|(time, task)| !task.completed() && task.overdue(past_due(*time), now))
+            .count();
+        if overdue > 0 {
+            header.push_styled(NORD_RED, BOLD, &format!(" {overdue} overdue"));
+        }
+        self.lines.push(header);
+
+        let progress = self.newline();
+        progress.push(NORD_WHITE, &format!("{done}/{total} done"));
+        progress.decoration = Some(Decoration::Progress(done as f32 / total.max(1) as f32));
+
+        let today = day == now.date_naive();
+        let mut now_marked = false;
+        let mut line = Line::default();
+        for (time, tasklist) in timeslots.iter() {
+            let all_done = tasklist.iter().all(|t| t.completed());
+            let past_due = past_due(*time);
+            let overdue = tasklist
+                .iter()
+                .any(|t| !t.completed() && t.overdue(past_due, now));
+
+            if today && !past_due && !now_marked {
+                self.render_now(now);
+                now_marked = true;
+            }
+
+            let bracket_color = match (all_done, overdue) {
+                (true, _) => NORD_GREEN,
+                (false, true) => NORD_RED,
+                (false, false) => NORD_BLUE,
+            };
+
+            // Draw the [time]
+            line.push(bracket_color, &format!("[{time}]"));
+
+            for task in tasklist {
+                self.push_task(&mut line, task, task.overdue(past_due, now));
+            }
+            self.lines.push(std::mem::take(&mut line));
+        }
+
+        if today && !now_marked {
+            self.render_now(now);
+        }
+    }
+
+    /// Adds ` (task)` to `line`.
+    fn push_task(&self, line: &mut Line, task: &Task, overdue: bool) {
+        let blocked = self.by_id.blocked(task);
+        let paren_color = match (task.completed(), blocked, overdue) {
+            (true, _, _) => NORD_GREEN,
+            (false, true, _) => NORD_PURPLE,
+            (false, false, true) => NORD_RED,
+            (false, false, false) => NORD_WHITE,
+        };
+        // Blocked tasks fade into the background until they're ready
+        let text_color = if blocked { NORD_PURPLE } else { NORD_WHITE };
+        line.push(paren_color, " (");
+        let marker_color = match task.priority() {
+            Priority::Urgent => Some(NORD_ORANGE),
+            Priority::High => Some(NORD_YELLOW),
+            Priority::Normal => None,
+            Priority::Low => Some(NORD_BLUE),
+        };
+        if let Some(color) = marker_color {
+            line.push_styled(color, BOLD, task.priority().marker());
+            line.push(NORD_WHITE, " ");
+        }
+        if task.completed() {
+            line.push_styled(NORD_WHITE, DONE, task.desc());
+        } else {
+            line.push(text_color, task.desc());
+        }
+        let details = task.details();
+        if details.has_attachments() {
+            let marker = match self.fonts.has_glyph(Face::Regular, ATTACHMENT) {
+                true => ATTACHMENT.to_string(),
+                false => "*".to_string(),
+            };
+            line.push(NORD_WHITE, " ");
+            line.push(NORD_BLUE, &marker);
+        }
+        // Subtasks are collapsed, there's no room for the whole tree
+        for (done, total) in [details.progress(), task.subtask_progress()]
+            .into_iter()
+            .flatten()
+        {
+            let color = if done == total { NORD_GREEN } else { NORD_BLUE };
+            line.push(NORD_WHITE, " ");
+            line.push(color, &format!("[{done}/{total}]"));
+        }
+        if let Some(deadline) = task.deadline() {
+            let color = if overdue && !task.completed() {
+                NORD_RED
+            } else {
+                NORD_YELLOW
+            };
+            line.push(NORD_WHITE, " ");
+            line.push(color, &format!("due {deadline}"));
+        }
+        for tag in task.tags() {
+            let color = self.tag_colors.get(&tag.to_lowercase());
+            line.push(NORD_WHITE, " ");
+            line.push(*color.unwrap_or(&NORD_CYAN), tag);
+        }
+        line.push(paren_color, ")");
+    }
+
+    /// Lists the unfinished tasks that are due soon, or should already be done.
+    fn render_deadlines(&mut self) {
+        let keeper = self.keeper;
+        let tasks = keeper.upcoming_deadlines(&self.filter);
+        if tasks.is_empty() {
+            return;
+        }
+
+        let now = keeper.clock().now();
+        self.newline().push_styled(NORD_WHITE, BOLD, "Deadlines");
+        for task in tasks {
+            let Some(deadline) = task.deadline() else {
+                continue;
+            };
+            let overdue = deadline.passed(now);
+            let color = if overdue { NORD_RED } else { NORD_YELLOW };
+            let mut line = Line::default();
+            line.push(color, &format!("[{}]", deadline.countdown(now)));
+            self.push_task(&mut line, task, overdue);
+            self.lines.push(line);
+        }
+    }
+
+    /// Lists the unfinished tasks in the inbox, with the index to schedule
+    /// them by.
+    fn render_inbox(&mut self) {
+        let keeper = self.keeper;
+        let tasks: Vec<_> = keeper
+            .inbox
+            .iter()
+            .enumerate()
+            .filter(|(_, task)| !task.completed() && self.filter.matches(task))
+            .collect();
+        if tasks.is_empty() {
+            return;
+        }
+
+        let header = self.newline();
+        header.push_styled(NORD_WHITE, BOLD, "Inbox");
+        header.push_styled(NORD_BLUE, BOLD, &format!(" {}", tasks.len()));
+        for (index, task) in tasks {
+            let mut line = Line::default();
+            line.push(NORD_BLUE, &format!("[{index}]"));
+            self.push_task(&mut line, task, false);
+            self.lines.push(line);
+        }
+    }
+
+    /// Marks the current time, between the slots that have passed and the ones
+    /// still to come.
+    fn render_now(&mut self, now: DateTime<Local>) {
+        let marker = self.newline();
+        marker.push(NORD_YELLOW, &format!("{}", now.format("%H:%M")));
+        marker.decoration = Some(Decoration::Rule(NORD_YELLOW));
+    }
+
+    pub fn render(&mut self) {
+        match self.selection {
+            ShowSet::Days(days) => {
+                for date in self.keeper.clock().today().iter_days().take(days) {
+                    self.render_day(date);
+                }
+            }
+            ShowSet::Date(date) => {
+                self.render_day(date);
+            }
+        }
+        self.render_deadlines();
+        if self.show_inbox {
+            self.render_inbox();
+        }
+        if self.lines.is_empty()
+            || self.image.width() < MIN_SIZE.0
+            || self.image.height() < MIN_SIZE.1
+        {
+            return;
+        }
+
+        // Take padding into account
+        let effective_height = (self.image.height() - 2 * Y_PAD - Y_START) as f32;
+        // Subtracting another 20 pixels prevents noticeable left-right asymmetry for some reason
+        let effective_width = (self.image.width() - 2 * X_PAD - 20) as f32;
+
+        let right_edge = X_PAD as f32 + effective_width;
+        let (scale, rows) = layout(self.fonts, &self.lines, effective_width, effective_height);
+        let row_height = self.fonts.line_height(scale);
+        let mut ypos = (Y_PAD + Y_START) as f32;
+        for row in rows {
+            let mut xpos = X_PAD as f32 + row.indent;
+            for span in &row.line.spans {
+                let start = xpos;
+                for (font, run) in self.fonts.runs(span.style.face, &span.text) {
+                    // Line up the baselines of fonts with different ascents
+                    let baseline_shift = self.fonts.ascent(scale) - font.v_metrics(scale).ascent;
+                    let (x, y) = (xpos as i32, (ypos + baseline_shift) as i32);
+                    let face = span.style.face;
+                    match (face, self.fonts.synthetic(face, font)) {
+                        (Face::Bold, true) => {
+                            // Smear it to the right
+                            let weight = (scale.x * FAKE_BOLD).round().max(1.0) as i32;
+                            for dx in 0..=weight {
+                                draw_text_mut(
+                                    &mut self.image,
+                                    span.color,
+                                    x + dx,
+                                    y,
+                                    scale,
+                                    font,
+                                    run,
+                                );
+                            }
+                        }
+                        (Face::Italic, true) => {
+                            draw_slanted_text(
+                                &mut self.image,
+                                span.color,
+                                (x, y),
+                                scale,
+                                font,
+                                run,
+                            );
+                        }
+                        _ => draw_text_mut(&mut self.image, span.color, x, y, scale, font, run),
+                    }
+                    xpos += self.fonts.advance(span.style.face, scale, run);
+                }
+
+                if span.style.strikethrough {
+                    let end = start
+                        + self
+                            .fonts
+                            .advance(span.style.face, scale, span.text.trim_end());
+                    self.strike(span.color, start, end, ypos, scale);
+                }
+            }
+
+            let start = xpos + scale.x * DECORATION_GAP;
+            match row.line.decoration {
+                Some(Decoration::Rule(color)) => {
+                    self.strike(color, start, right_edge, ypos, scale);
+                }
+                Some(Decoration::Progress(fraction)) => {
+                    self.progress_bar(fraction, start, right_edge, ypos, scale);
+                }
+                None => {}
+            }
+            ypos += row_height;
+        }
+    }
+
+    /// Draws a bar spanning `start..end` on the row at `ypos`, filled up to
+    /// `fraction`.
+    fn progress_bar(&mut self, fraction: f32, start: f32, end: f32, ypos: f32, scale: Scale) {
+        let ascent = self.fonts.ascent(scale);
+        // About as tall as a lowercase letter, sitting on the baseline
+        let top = (ypos + ascent * 0.45) as i32;
+        let height = (ascent * 0.55).max(1.0) as u32;
+        let width = (end - start).max(0.0);
+        let filled = (width * fraction.clamp(0.0, 1.0)).round() as u32;
+        let width = width.round() as u32;
+        if width == 0 {
+            return;
+        }
+
+        draw_filled_rect_mut(
+            &mut self.image,
+            Rect::at(start as i32, top).of_size(width, height),
+            NORD_GREY,
+        );
+        if filled > 0 {
+            draw_filled_rect_mut(
+                &mut self.image,
+                Rect::at(start as i32, top).of_size(filled, height),
+                NORD_GREEN,
+            );
+        }
+    }
+
+    /// Draws a line spanning `start..end` through the text on the row at `ypos`.
+    fn strike(&mut self, color: Rgb<u8>, start: f32, end: f32, ypos: f32, scale: Scale) {
+        // Roughly through the middle of lowercase letters
+        let y = ypos + self.fonts.ascent(scale) * 0.65;
+        let thickness = (scale.y / 14.0).max(1.0);
+        let width = (end - start).round() as u32;
+        if width == 0 {
+            return;
+        }
+        draw_filled_rect_mut(
+            &mut self.image,
+            Rect::at(start as i32, y as i32).of_size(width, thickness as u32),
+            color,
+        );
+    }
+
+    pub fn image(&self) -> &ImageBuffer<Rgb<u8>, Vec<u8>> {
+        &self.image
+    }
+
+    pub fn save(&mut self, path: &Path) -> anyhow::Result<()> {
+        self.image
+            .save(path)
+            .with_context(|| format!("failed to save image to {path:?}"))
+    }
+}
+
+/// Like `draw_text_mut`, but leaning to the right, for fonts without an
+/// italic of their own.
+fn draw_slanted_text(
+    image: &mut RgbImage,
+    color: Rgb<u8>,
+    (x, y): (i32, i32),
+    scale: Scale,
+    font: &Font,
+    text: &str,
+) {
+    let ascent = font.v_metrics(scale).ascent;
+    let (width, height) = (image.width() as i32, image.height() as i32);
+    for glyph in font.layout(text, scale, point(0.0, ascent)) {
+        let Some(bounds) = glyph.pixel_bounding_box() else {
+            continue;
+        };
+        glyph.draw(|gx, gy, coverage| {
+            let gy = gy as i32 + bounds.min.y;
+            let lean = ((ascent - gy as f32) * FAKE_SLANT).round() as i32;
+            let (px, py) = (x + gx as i32 + bounds.min.x + lean, y + gy);
+            if (0..width).contains(&px) && (0..height).contains(&py) {
+                let pixel = image.get_pixel_mut(px as u32, py as u32);
+                *pixel = weighted_sum(*pixel, color, 1.0 - coverage, coverage);
+            }
+        });
+    }
+}
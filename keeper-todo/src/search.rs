@@ -0,0 +1,191 @@
+use chrono::NaiveDate;
+use regex::Regex;
+
+use crate::{
+    data::{past_due, Keeper, Task},
+    filter::Filter,
+};
+
+/// How the query is matched against tasks. All of them ignore case.
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    /// The query appears somewhere in the description, tags or notes.
+    Substring(String),
+    /// Like [`Pattern::Substring`], but with a regex.
+    Regex(Regex),
+    /// The letters of the query appear in order in the description, e.g.
+    /// `dntst` finds "dentist".
+    Fuzzy(String),
+}
+
+impl Pattern {
+    /// How well `task` matches, higher is better, or `None` if it doesn't.
+    pub fn score(&self, task: &Task) -> Option<usize> {
+        match self {
+            Self::Substring(query) => haystack(task).contains(&query.to_lowercase()).then_some(0),
+            Self::Regex(regex) => regex.is_match(&haystack(task)).then_some(0),
+            Self::Fuzzy(query) => fuzzy(query, task.desc()),
+        }
+    }
+}
+
+/// Everything about a task worth searching through, lowercased.
+fn haystack(task: &Task) -> String {
+    let mut text = task.desc().to_string();
+    for tag in task.tags() {
+        text.push('\n');
+        text.push_str(tag);
+    }
+    text.push('\n');
+    text.push_str(&task.details().to_text());
+    text.to_lowercase()
+}
+
+/// Scores a subsequence match of `query` in `text`, preferring letters that
+/// are next to each other or start a word. Spaces in the query are ignored.
+fn fuzzy(query: &str, text: &str) -> Option<usize> {
+    let text: Vec<_> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut next = 0;
+    for c in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let i = next + text[next..].iter().position(|t| *t == c)?;
+        score += 1;
+        if i > 0 && i == next {
+            score += 2;
+        }
+        if i == 0 || !text[i - 1].is_alphanumeric() {
+            score += 3;
+        }
+        next = i + 1;
+    }
+    Some(score)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Done,
+    Todo,
+    Overdue,
+    Blocked,
+}
+
+impl Status {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "done" | "complete" | "completed" => Some(Self::Done),
+            "todo" | "open" | "pending" => Some(Self::Todo),
+            "overdue" | "late" => Some(Self::Overdue),
+            "blocked" => Some(Self::Blocked),
+            _ => None,
+        }
+    }
+}
+
+/// What to look for with `keeper-todo search`.
+#[derive(Debug, Clone)]
+pub struct Search {
+    pub pattern: Pattern,
+    pub filter: Filter,
+    pub status: Option<Status>,
+    /// Only tasks planned on or after this date. Leaves out the inbox.
+    pub from: Option<NaiveDate>,
+    /// Only tasks planned on or before this date. Leaves out the inbox.
+    pub to: Option<NaiveDate>,
+}
+
+impl Search {
+    /// A search for `pattern` without any other conditions.
+    pub fn new(pattern: Pattern) -> Self {
+        Self {
+            pattern,
+            filter: Filter::default(),
+            status: None,
+            from: None,
+            to: None,
+        }
+    }
+
+    /// The matching tasks, including subtasks and the inbox. Fuzzy matches come
+    /// best first, everything else in the order `list` shows it.
+    pub fn hits<'a>(&self, keeper: &'a Keeper) -> Vec<Hit<'a>> {
+        let now = keeper.clock().now();
+        let by_id = keeper.index();
+        let mut hits = Vec::new();
+        let mut check = |slot: Option<(NaiveDate, usize)>, path: &[usize], task: &'a Task| {
+            let overdue = match slot {
+                Some((date, hour)) => task.overdue(past_due(date, hour, now), now),
+                None => task.overdue(false, now),
+            };
+            let status = match self.status {
+                None => true,
+                Some(Status::Done) => task.completed(),
+                Some(Status::Todo) => !task.completed(),
+                Some(Status::Overdue) => !task.completed() && overdue,
+                Some(Status::Blocked) => by_id.blocked(task),
+            };
+            if !status || !self.filter.matches(task) {
+                return;
+            }
+            if let Some(score) = self.pattern.score(task) {
+                hits.push(Hit {
+                    slot,
+                    path: path.to_vec(),
+                    task,
+                    overdue,
+                    score,
+                });
+            }
+        };
+
+        let dated = self.from.is_some() || self.to.is_some();
+        for (date, schedule) in &keeper.days {
+            if self.from.is_some_and(|from| *date < from) || self.to.is_some_and(|to| *date > to) {
+                continue;
+            }
+            for (hour, tasks) in &schedule.timeslots {
+                for (index, task) in tasks.iter().enumerate() {
+                    walk(task, &mut vec![index], &mut |path, task| {
+                        check(Some((*date, *hour)), path, task)
+                    });
+                }
+            }
+        }
+        if !dated {
+            for (index, task) in keeper.inbox.iter().enumerate() {
+                // Subtasks in the inbox can't be referred to by any command
+                check(None, &[index], task);
+            }
+        }
+
+        if matches!(self.pattern, Pattern::Fuzzy(_)) {
+            hits.sort_by_key(|hit| std::cmp::Reverse(hit.score));
+        }
+        hits
+    }
+}
+
+/// Calls `f` with `task` and each of its subtasks, and their paths.
+pub(crate) fn walk<'a>(
+    task: &'a Task,
+    path: &mut Vec<usize>,
+    f: &mut impl FnMut(&[usize], &'a Task),
+) {
+    f(path, task);
+    for (index, subtask) in task.subtasks().iter().enumerate() {
+        path.push(index);
+        walk(subtask, path, f);
+        path.pop();
+    }
+}
+
+/// A task found by a [`Search`].
+#[derive(Debug)]
+pub struct Hit<'a> {
+    /// The date and hour the task is planned for, or `None` in the inbox.
+    pub slot: Option<(NaiveDate, usize)>,
+    /// The task's index in its hour (or the inbox), then subtask indices.
+    pub path: Vec<usize>,
+    pub task: &'a Task,
+    pub overdue: bool,
+    score: usize,
+}
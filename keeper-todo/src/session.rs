@@ -0,0 +1,109 @@
+//! Running many commands against one loaded `Keeper`, from a file with `batch`
+//! or typed at the `repl` prompt.
+
+use anyhow::{bail, Context};
+use keeper_util::clock::Clock;
+use rustyline::{
+    completion::Completer, highlight::Highlighter, hint::Hinter, validate::Validator, Helper,
+};
+
+use crate::{cli::Command, data::Keeper};
+
+const PROGRAM: &str = "keeper-todo";
+const COMMANDS: &[&str] = &[
+    "add", "inbox", "schedule", "mark", "change", "delete", "show", "render", "list", "history",
+    "stats", "search", "note", "due", "depend", "undepend", "export", "import", "notify", "status",
+    "snooze", "remind",
+];
+const DATE_WORDS: &[&str] = &["today", "tomorrow", "yesterday"];
+/// What ends the REPL, besides Ctrl-D.
+pub const QUIT: &[&str] = &["quit", "exit"];
+
+/// Splits `line` into arguments the way a shell would and parses them, so
+/// quotes work like on the command line. The `keeper-todo` in front is
+/// optional. Blank lines and `#` comments are `None`.
+///
+/// A line that doesn't parse is `fatal!`, so this should be run inside
+/// [`keeper_util::recover`].
+pub fn parse_line(line: &str, clock: Clock) -> anyhow::Result<Option<Command>> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+    let mut args = shell_words::split(line).with_context(|| format!("failed to split [{line}]"))?;
+    if args.first().is_some_and(|arg| arg == PROGRAM) {
+        args.remove(0);
+    }
+    // `Command::parse` skips the program itself
+    let command = Command::parse([PROGRAM.to_string()].into_iter().chain(args), clock);
+    match command {
+        Command::Daemon
+        | Command::Status { watch: true, .. }
+        | Command::Tui
+        | Command::Batch { .. }
+        | Command::Repl => bail!("[{line}] only works on its own"),
+        command => Ok(Some(command)),
+    }
+}
+
+/// Tab completion for the REPL: command names first, then dates, and tags
+/// already in use for words starting with `+` or `@`.
+pub struct LineHelper {
+    tags: Vec<String>,
+}
+
+impl LineHelper {
+    pub fn new(keeper: &Keeper) -> Self {
+        let mut tags: Vec<_> = keeper
+            .tasks()
+            .iter()
+            .flat_map(|task| task.tags())
+            .cloned()
+            .collect();
+        tags.sort();
+        tags.dedup();
+        Self { tags }
+    }
+
+    /// Where the word before `pos` starts, and what it could be.
+    pub fn candidates(&self, line: &str, pos: usize) -> (usize, Vec<String>) {
+        let start = line[..pos].rfind(' ').map_or(0, |space| space + 1);
+        let word = &line[start..pos];
+        let options: Vec<&str> = if line[..start].trim().is_empty() {
+            COMMANDS.iter().chain(QUIT).copied().collect()
+        } else if word.starts_with(['+', '@']) {
+            self.tags.iter().map(String::as_str).collect()
+        } else {
+            DATE_WORDS.to_vec()
+        };
+        let candidates = options
+            .into_iter()
+            .filter(|option| option.starts_with(word))
+            .map(str::to_string)
+            .collect();
+        (start, candidates)
+    }
+}
+
+impl Completer for LineHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &rustyline::Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(self.candidates(line, pos))
+    }
+}
+
+impl Hinter for LineHelper {
+    type Hint = String;
+}
+
+impl Highlighter for LineHelper {}
+
+impl Validator for LineHelper {}
+
+impl Helper for LineHelper {}
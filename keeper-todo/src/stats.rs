@@ -0,0 +1,365 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use chrono::{Datelike, Days, NaiveDate};
+use image::{Rgb, RgbImage};
+use imageproc::{
+    drawing::{draw_filled_rect_mut, draw_text_mut},
+    rect::Rect,
+};
+use keeper_util::color::{GREEN, RESET, YELLOW};
+use rusttype::Scale;
+
+use crate::{
+    data::{past_due, ColorStyle, Keeper, Schedule, Task},
+    font::{Face, Fonts},
+    render::{NORD_BG, NORD_GREEN, NORD_GREY, NORD_WHITE},
+};
+
+/// How many of the busiest hours and most deferred tasks to report.
+const TOP: usize = 5;
+
+/// What to group completion rates by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Period {
+    Day,
+    #[default]
+    Week,
+    Month,
+}
+
+impl Period {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "day" | "daily" => Some(Self::Day),
+            "week" | "weekly" => Some(Self::Week),
+            "month" | "monthly" => Some(Self::Month),
+            _ => None,
+        }
+    }
+
+    /// The first day of the period `date` is in. Weeks start on Monday.
+    fn start(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Self::Day => date,
+            Self::Week => date - Days::new(date.weekday().num_days_from_monday().into()),
+            Self::Month => date.with_day(1).unwrap(),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Day => "day",
+            Self::Week => "week",
+            Self::Month => "month",
+        }
+    }
+
+    fn label(&self, start: NaiveDate) -> String {
+        match self {
+            Self::Day | Self::Week => start.format("%d %b").to_string(),
+            Self::Month => start.format("%b %Y").to_string(),
+        }
+    }
+}
+
+/// How a single day went.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DayStats {
+    pub date: NaiveDate,
+    pub total: usize,
+    pub done: usize,
+    /// Unfinished tasks that are late by now.
+    pub overdue: usize,
+}
+
+/// Completion rates and habits over the last few days. Only top-level tasks
+/// count, like in the wallpaper's progress bar.
+pub struct Stats<'a> {
+    pub days: Vec<DayStats>,
+    /// How many tasks were planned for each hour.
+    pub hours: BTreeMap<usize, usize>,
+    /// Tasks that have been pushed back, most often first.
+    pub deferred: Vec<&'a Task>,
+    today: NaiveDate,
+}
+
+impl<'a> Stats<'a> {
+    /// Stats for the `days` days up to and including today.
+    pub fn new(keeper: &'a Keeper, days: usize) -> Self {
+        let now = keeper.clock().now();
+        let today = now.date_naive();
+        let empty = Schedule::default();
+        let mut hours = BTreeMap::new();
+        let days = (0..days as u64)
+            .rev()
+            .map(|ago| {
+                let date = today - Days::new(ago);
+                let timeslots = &keeper.days.get(&date).unwrap_or(&empty).timeslots;
+                let mut day = DayStats {
+                    date,
+                    total: 0,
+                    done: 0,
+                    overdue: 0,
+                };
+                for (hour, tasks) in timeslots {
+                    *hours.entry(*hour).or_default() += tasks.len();
+                    for task in tasks {
+                        day.total += 1;
+                        day.done += task.completed() as usize;
+                        let late = task.overdue(past_due(date, *hour, now), now);
+                        day.overdue += (!task.completed() && late) as usize;
+                    }
+                }
+                day
+            })
+            .collect();
+
+        let mut deferred: Vec<_> = keeper
+            .tasks()
+            .into_iter()
+            .filter(|task| task.deferred() > 0)
+            .collect();
+        deferred.sort_by_key(|task| std::cmp::Reverse(task.deferred()));
+        deferred.truncate(TOP);
+
+        Self {
+            days,
+            hours,
+            deferred,
+            today,
+        }
+    }
+
+    /// `(label, done, total)` for each period, oldest first.
+    pub fn by(&self, period: Period) -> Vec<(String, usize, usize)> {
+        self.periods(period)
+            .into_iter()
+            .map(|(start, done, total)| (period.label(start), done, total))
+            .collect()
+    }
+
+    /// Like [`Stats::by`], but with the day each period starts on.
+    pub fn periods(&self, period: Period) -> Vec<(NaiveDate, usize, usize)> {
+        let mut periods: BTreeMap<NaiveDate, (usize, usize)> = BTreeMap::new();
+        for day in &self.days {
+            let (done, total) = periods.entry(period.start(day.date)).or_default();
+            *done += day.done;
+            *total += day.total;
+        }
+        periods
+            .into_iter()
+            .map(|(start, (done, total))| (start, done, total))
+            .collect()
+    }
+
+    /// Overdue tasks per day, over the days that had any tasks.
+    pub fn average_overdue(&self) -> f32 {
+        let days: Vec<_> = self.days.iter().filter(|day| day.total > 0).collect();
+        let overdue: usize = days.iter().map(|day| day.overdue).sum();
+        overdue as f32 / days.len().max(1) as f32
+    }
+
+    /// `(hour, tasks)` for the hours with the most tasks, busiest first.
+    pub fn busiest_hours(&self) -> Vec<(usize, usize)> {
+        let mut hours: Vec<_> = self.hours.iter().map(|(h, n)| (*h, *n)).collect();
+        hours.sort_by_key(|(hour, tasks)| (std::cmp::Reverse(*tasks), *hour));
+        hours.truncate(TOP);
+        hours
+    }
+
+    /// The current and longest runs of days where everything got done. Days
+    /// without tasks don't break a streak, and neither does today while it's
+    /// still going.
+    pub fn streaks(&self) -> (usize, usize) {
+        let (mut current, mut longest) = (0, 0);
+        for day in self.days.iter().filter(|day| day.total > 0) {
+            if day.done == day.total {
+                current += 1;
+                longest = longest.max(current);
+            } else if day.date != self.today {
+                current = 0;
+            }
+        }
+        (current, longest)
+    }
+
+    /// A bar per period, showing how many tasks were planned and how many of
+    /// them got done.
+    pub fn chart(&self, period: Period, fonts: &Fonts, (width, height): (u32, u32)) -> RgbImage {
+        let mut image = RgbImage::from_pixel(width, height, NORD_BG);
+        let (width, height) = (width as f32, height as f32);
+        let pad = height / 12.0;
+        let title = Scale::uniform(pad * 0.6);
+        let label = Scale::uniform(pad * 0.4);
+        let days = self.days.len();
+        draw(
+            &mut image,
+            fonts,
+            NORD_WHITE,
+            (pad, pad / 2.0),
+            title,
+            &format!("Tasks done per {}, last {days} days", period.name()),
+        );
+
+        let periods = self.by(period);
+        let most = periods
+            .iter()
+            .map(|(_, _, total)| *total)
+            .max()
+            .unwrap_or(0);
+        if most == 0 {
+            draw(
+                &mut image,
+                fonts,
+                NORD_WHITE,
+                (pad, pad * 2.0),
+                label,
+                "No tasks",
+            );
+            return image;
+        }
+
+        let (left, right) = (pad, width - pad);
+        let (top, bottom) = (pad * 2.5, height - pad * 1.5);
+        let slot = (right - left) / periods.len() as f32;
+        let bar_width = (slot * 0.6).max(1.0);
+        for (i, (name, done, total)) in periods.iter().enumerate() {
+            let x = left + slot * i as f32 + (slot - bar_width) / 2.0;
+            let bar = |count: usize| (bottom - top) * count as f32 / most as f32;
+            fill(
+                &mut image,
+                NORD_GREY,
+                x,
+                bottom - bar(*total),
+                bar_width,
+                bar(*total),
+            );
+            fill(
+                &mut image,
+                NORD_GREEN,
+                x,
+                bottom - bar(*done),
+                bar_width,
+                bar(*done),
+            );
+
+            let rate = format!("{}%", percent(*done, *total));
+            let above = bottom - bar(*total) - fonts.line_height(label);
+            draw(&mut image, fonts, NORD_WHITE, (x, above), label, &rate);
+            draw(
+                &mut image,
+                fonts,
+                NORD_WHITE,
+                (x, bottom + pad / 4.0),
+                label,
+                name,
+            );
+        }
+        image
+    }
+}
+
+pub(crate) fn percent(done: usize, total: usize) -> usize {
+    (100.0 * done as f32 / total.max(1) as f32).round() as usize
+}
+
+fn fill(image: &mut RgbImage, color: Rgb<u8>, x: f32, y: f32, width: f32, height: f32) {
+    if height < 1.0 {
+        return;
+    }
+    let rect = Rect::at(x as i32, y as i32).of_size(width as u32, height as u32);
+    draw_filled_rect_mut(image, rect, color);
+}
+
+fn draw(
+    image: &mut RgbImage,
+    fonts: &Fonts,
+    color: Rgb<u8>,
+    (mut x, y): (f32, f32),
+    scale: Scale,
+    text: &str,
+) {
+    for (font, run) in fonts.runs(Face::Regular, text) {
+        let baseline_shift = fonts.ascent(scale) - font.v_metrics(scale).ascent;
+        draw_text_mut(
+            image,
+            color,
+            x as i32,
+            (y + baseline_shift) as i32,
+            scale,
+            font,
+            run,
+        );
+        x += fonts.advance(Face::Regular, scale, run);
+    }
+}
+
+/// The stats as text tables for the terminal.
+pub struct StatsReport<'a> {
+    stats: &'a Stats<'a>,
+    period: Period,
+    color: ColorStyle,
+}
+
+impl<'a> StatsReport<'a> {
+    pub fn new(stats: &'a Stats<'a>, period: Period, color: ColorStyle) -> Self {
+        Self {
+            stats,
+            period,
+            color,
+        }
+    }
+}
+
+impl Display for StatsReport<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (yellow, green, reset) = match self.color {
+            ColorStyle::Color => (YELLOW, GREEN, RESET),
+            ColorStyle::NoColor => ("", "", ""),
+        };
+        let stats = self.stats;
+
+        let heading = match self.period {
+            Period::Week => "week of",
+            period => period.name(),
+        };
+        writeln!(f, "{yellow}Completion{reset}")?;
+        writeln!(
+            f,
+            "{heading:<10} {:>5} {:>5} {:>5}",
+            "done", "total", "rate"
+        )?;
+        for (label, done, total) in stats.by(self.period) {
+            let rate = format!("{}%", percent(done, total));
+            writeln!(f, "{label:<10} {done:>5} {total:>5} {rate:>5}")?;
+        }
+
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{yellow}Overdue{reset} {:.1} per day",
+            stats.average_overdue()
+        )?;
+        let (current, longest) = stats.streaks();
+        writeln!(
+            f,
+            "{yellow}Streak{reset} {green}{current}{reset} days all done, longest {longest}"
+        )?;
+
+        writeln!(f)?;
+        writeln!(f, "{yellow}Busiest hours{reset}")?;
+        writeln!(f, "{:<10} {:>5}", "hour", "tasks")?;
+        for (hour, tasks) in stats.busiest_hours() {
+            writeln!(f, "{:<10} {tasks:>5}", format!("[{hour}]"))?;
+        }
+
+        if !stats.deferred.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "{yellow}Most deferred{reset}")?;
+            for task in &stats.deferred {
+                writeln!(f, "{}x ({})", task.deferred(), task.desc())?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,249 @@
+//! What to put in a status bar: the next task and how many are late, for
+//! waybar, polybar, i3blocks and tmux.
+
+use std::sync::mpsc::{self, RecvTimeoutError};
+
+use anyhow::{bail, Context};
+use chrono::NaiveDate;
+use keeper_util::{clock::Clock, DataManager};
+use notify::{RecursiveMode, Watcher};
+use serde::Serialize;
+
+use crate::{
+    daemon::{next_refresh, DEBOUNCE},
+    data::{past_due, Keeper, Task, TaskStatus},
+};
+
+const RED: &str = "#BF616A";
+
+/// Which bar to write for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Bar {
+    #[default]
+    Plain,
+    Waybar,
+    Polybar,
+    I3blocks,
+    Tmux,
+}
+
+impl Bar {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "plain" | "text" => Some(Self::Plain),
+            "waybar" => Some(Self::Waybar),
+            "polybar" => Some(Self::Polybar),
+            "i3blocks" | "i3" => Some(Self::I3blocks),
+            "tmux" => Some(Self::Tmux),
+            _ => None,
+        }
+    }
+}
+
+/// The next thing to do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Next {
+    pub date: NaiveDate,
+    pub hour: usize,
+    pub desc: String,
+}
+
+/// Everything the bar shows, so it only has to be updated when this changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BarState {
+    /// The first unfinished task from today on that's neither late nor
+    /// waiting on anything.
+    pub next: Option<Next>,
+    /// Unfinished tasks that are late, on any day or in the inbox.
+    pub overdue: usize,
+    /// `(hour, desc, status)` for each of today's tasks, for the tooltip.
+    pub today: Vec<(usize, String, TaskStatus)>,
+    today_date: NaiveDate,
+}
+
+impl BarState {
+    /// Only top-level tasks count, like in `show`.
+    pub fn new(keeper: &Keeper) -> Self {
+        let now = keeper.clock().now();
+        let today_date = now.date_naive();
+        let by_id = keeper.index();
+        let status = |date, hour, task: &Task| {
+            TaskStatus::of(task, task.overdue(past_due(date, hour, now), now))
+        };
+
+        let mut next = None;
+        let mut overdue = 0;
+        let mut today = Vec::new();
+        for (date, schedule) in &keeper.days {
+            for (hour, tasks) in &schedule.timeslots {
+                for task in tasks {
+                    let status = status(*date, *hour, task);
+                    overdue += (status == TaskStatus::Overdue) as usize;
+                    if *date == today_date {
+                        today.push((*hour, task.desc().to_string(), status));
+                    }
+                    if next.is_none()
+                        && *date >= today_date
+                        && status == TaskStatus::Pending
+                        && !by_id.blocked(task)
+                    {
+                        next = Some(Next {
+                            date: *date,
+                            hour: *hour,
+                            desc: task.desc().to_string(),
+                        });
+                    }
+                }
+            }
+        }
+        // Only a deadline can make something in the inbox late
+        overdue += keeper
+            .inbox
+            .iter()
+            .filter(|task| !task.completed() && task.overdue(false, now))
+            .count();
+
+        Self {
+            next,
+            overdue,
+            today,
+            today_date,
+        }
+    }
+
+    fn next_text(&self) -> Option<String> {
+        let next = self.next.as_ref()?;
+        let day = match next.date == self.today_date {
+            true => String::new(),
+            false => next.date.format("%a ").to_string(),
+        };
+        Some(format!("{day}{}:00 {}", next.hour, next.desc))
+    }
+
+    fn overdue_text(&self) -> Option<String> {
+        (self.overdue > 0).then(|| format!("{} overdue", self.overdue))
+    }
+
+    /// The overdue count styled by `overdue`, then the next task styled by
+    /// `next`.
+    fn text(&self, overdue: impl Fn(String) -> String, next: impl Fn(String) -> String) -> String {
+        let parts: Vec<_> = [self.overdue_text().map(overdue), self.next_text().map(next)]
+            .into_iter()
+            .flatten()
+            .collect();
+        match parts.is_empty() {
+            true => "nothing left".to_string(),
+            false => parts.join(" · "),
+        }
+    }
+
+    fn tooltip(&self) -> String {
+        if self.today.is_empty() {
+            return "Nothing planned today".to_string();
+        }
+        self.today
+            .iter()
+            .map(|(hour, desc, status)| {
+                let mark = match status {
+                    TaskStatus::Done => 'x',
+                    TaskStatus::Overdue => '!',
+                    TaskStatus::Pending => ' ',
+                };
+                format!("[{mark}] {hour}:00 {desc}")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// A single line for `bar`.
+    pub fn format(&self, bar: Bar) -> String {
+        match bar {
+            Bar::Plain => self.text(|overdue| overdue, |next| next),
+            Bar::Waybar => {
+                #[derive(Serialize)]
+                struct Waybar {
+                    text: String,
+                    tooltip: String,
+                    class: &'static str,
+                }
+                let class = match (self.overdue > 0, &self.next) {
+                    (true, _) => "overdue",
+                    (false, Some(_)) => "pending",
+                    (false, None) => "done",
+                };
+                // Waybar takes both as pango markup
+                let waybar = Waybar {
+                    text: self.text(|overdue| overdue, |next| pango(&next)),
+                    tooltip: pango(&self.tooltip()),
+                    class,
+                };
+                serde_json::to_string(&waybar).unwrap()
+            }
+            Bar::Polybar => self.text(
+                |overdue| format!("%{{F{RED}}}{overdue}%{{F-}}"),
+                |next| next,
+            ),
+            // For markup=pango
+            Bar::I3blocks => self.text(
+                |overdue| format!("<span color=\"{RED}\">{overdue}</span>"),
+                |next| pango(&next),
+            ),
+            Bar::Tmux => self.text(
+                |overdue| format!("#[fg=red]{overdue}#[fg=default]"),
+                // tmux would take it for a format
+                |next| next.replace('#', "##"),
+            ),
+        }
+    }
+}
+
+fn pango(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Prints a line for `bar`, then another every time it would change, as
+/// tasks go overdue or the data file changes. Never returns unless the
+/// watcher dies.
+pub fn watch(dm: &DataManager<Keeper>, clock: Clock, bar: Bar) -> anyhow::Result<()> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).context("failed to create file watcher")?;
+    watcher
+        .watch(dm.data_dir(), RecursiveMode::NonRecursive)
+        .with_context(|| format!("failed to watch {:?}", dm.data_dir()))?;
+
+    let mut last = None;
+    loop {
+        match dm.load_data() {
+            Ok(mut keeper) => {
+                keeper.set_clock(clock);
+                let line = BarState::new(&keeper).format(bar);
+                if last.as_ref() != Some(&line) {
+                    println!("{line}");
+                    last = Some(line);
+                }
+            }
+            // stdout belongs to the bar
+            Err(e) => eprintln!("failed to load data: {e:#}"),
+        }
+
+        // Wait for the next hour or a change to the data file
+        let now = clock.now();
+        let timeout = (next_refresh(now) - now).to_std().unwrap_or_default();
+        match rx.recv_timeout(timeout) {
+            Ok(Ok(event)) => {
+                if event
+                    .paths
+                    .iter()
+                    .any(|path| path.ends_with(dm.data_filename()))
+                {
+                    while rx.recv_timeout(DEBOUNCE).is_ok() {}
+                }
+            }
+            Ok(Err(e)) => eprintln!("file watcher error: {e}"),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => bail!("file watcher stopped"),
+        }
+    }
+}
@@ -0,0 +1,204 @@
+//! [todo.txt](https://github.com/todotxt/todo.txt) import and export. The
+//! date a task is planned for goes in the `t:` (threshold) extension and its
+//! hour in `time:`, so other todo.txt tools hide tasks until their day.
+//! Deadlines go in `due:`, with their hour in `duetime:`.
+
+use anyhow::{bail, Context};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+
+use crate::{
+    data::{Deadline, ImportedTask, Keeper, Priority, Task, ALL_DAY_HOUR},
+    filter::is_tag,
+};
+
+const DATE: &str = "%Y-%m-%d";
+
+/// Writes the tasks planned between `from` and `to` (inclusive, and either
+/// open-ended) one per line. Without a range, the inbox is included too.
+/// Subtasks have nowhere to go in todo.txt, so they're left out.
+pub fn export(keeper: &Keeper, from: Option<NaiveDate>, to: Option<NaiveDate>) -> String {
+    let mut out = String::new();
+    for (date, schedule) in &keeper.days {
+        if from.is_some_and(|from| *date < from) || to.is_some_and(|to| *date > to) {
+            continue;
+        }
+        for (hour, tasks) in &schedule.timeslots {
+            for task in tasks {
+                out.push_str(&line(task, Some((*date, *hour))));
+                out.push('\n');
+            }
+        }
+    }
+    if from.is_none() && to.is_none() {
+        for task in &keeper.inbox {
+            out.push_str(&line(task, None));
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn line(task: &Task, slot: Option<(NaiveDate, usize)>) -> String {
+    let letter = match task.priority() {
+        Priority::Urgent => Some('A'),
+        Priority::High => Some('B'),
+        Priority::Normal => None,
+        Priority::Low => Some('C'),
+    };
+    let created = task
+        .created()
+        .map(|created| created.format(DATE).to_string());
+
+    let mut words = Vec::new();
+    if task.completed() {
+        words.push("x".to_string());
+        // The creation date can only come after a completion date
+        if let Some(completed_at) = task.completed_at() {
+            words.push(completed_at.format(DATE).to_string());
+            words.extend(created);
+        }
+    } else {
+        words.extend(letter.map(|letter| format!("({letter})")));
+        words.extend(created);
+    }
+    words.push(task.desc().to_string());
+    words.extend(task.tags().iter().cloned());
+    if let Some((date, hour)) = slot {
+        words.push(format!("t:{}", date.format(DATE)));
+        words.push(format!("time:{hour}:00"));
+    }
+    if let Some(deadline) = task.deadline() {
+        words.push(format!("due:{}", deadline.date.format(DATE)));
+        if let Some(hour) = deadline.hour {
+            words.push(format!("duetime:{hour}:00"));
+        }
+    }
+    // Done tasks lose their priority, so it's kept as an extension
+    if let Some(letter) = letter.filter(|_| task.completed()) {
+        words.push(format!("pri:{letter}"));
+    }
+    words.join(" ")
+}
+
+/// Reads a todo.txt file. Blank lines are skipped.
+pub fn import(text: &str, now: DateTime<Local>) -> anyhow::Result<Vec<ImportedTask>> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(number, line)| {
+            imported(line, now).with_context(|| format!("invalid line {}", number + 1))
+        })
+        .collect()
+}
+
+fn imported(line: &str, now: DateTime<Local>) -> anyhow::Result<ImportedTask> {
+    let words: Vec<_> = line.split_whitespace().collect();
+    let mut rest = &words[..];
+    let completed = rest.first() == Some(&"x");
+    if completed {
+        rest = &rest[1..];
+    }
+    let mut letter = None;
+    if let Some(l) = rest.first().and_then(|word| priority_letter(word)) {
+        letter = Some(l);
+        rest = &rest[1..];
+    }
+    // Done tasks have the completion date first, then the creation date
+    let mut dates = Vec::new();
+    while dates.len() < 1 + completed as usize {
+        match rest
+            .first()
+            .map(|word| NaiveDate::parse_from_str(word, DATE))
+        {
+            Some(Ok(date)) => dates.push(date),
+            _ => break,
+        }
+        rest = &rest[1..];
+    }
+    let (completed_on, created_on) = match completed {
+        true => (dates.first().copied(), dates.get(1).copied()),
+        false => (None, dates.first().copied()),
+    };
+
+    let mut desc = Vec::new();
+    let mut tags = Vec::new();
+    let (mut date, mut hour, mut due, mut due_hour) = (None, None, None, None);
+    for word in rest {
+        if is_tag(word) {
+            tags.push(*word);
+            continue;
+        }
+        match word.split_once(':') {
+            Some(("t", value)) => date = Some(parse_date(value)?),
+            Some(("time", value)) => hour = Some(parse_hour(value)?),
+            Some(("due", value)) => due = Some(parse_date(value)?),
+            Some(("duetime", value)) => due_hour = Some(parse_hour(value)?),
+            Some(("pri", value)) if letter.is_none() => {
+                letter = value.chars().next().filter(char::is_ascii_uppercase)
+            }
+            _ => desc.push(*word),
+        }
+    }
+    if desc.is_empty() {
+        bail!("no description in '{line}'");
+    }
+
+    let priority = match letter {
+        Some('A') => Priority::Urgent,
+        Some('B') => Priority::High,
+        Some(_) => Priority::Low,
+        None => Priority::Normal,
+    };
+    let mut task = Task::with_priority(desc.join(" "), priority);
+    for tag in tags {
+        task.add_tag(tag);
+    }
+    let local = |time: NaiveDateTime| Local.from_local_datetime(&time).earliest().unwrap_or(now);
+    if let Some(created) = created_on {
+        task.touch(local(created.and_time(NaiveTime::MIN)));
+    }
+    let slot = date.map(|date| (date, hour.unwrap_or(ALL_DAY_HOUR)));
+    if completed {
+        // Like tasks from before completion times were recorded, done by the
+        // end of their hour, if they got done on the day
+        let at = completed_on.map(|on| match slot {
+            Some((date, hour)) if date == on => on.and_hms_opt(hour as u32, 59, 59).unwrap(),
+            _ => on.and_hms_opt(12, 0, 0).unwrap(),
+        });
+        task.mark_complete(at.map_or(now, local));
+    }
+    if let Some(date) = due {
+        task.set_deadline(Some(Deadline {
+            date,
+            hour: due_hour,
+        }));
+    }
+    Ok(ImportedTask {
+        task,
+        slot,
+        parent: None,
+    })
+}
+
+/// The letter in a priority like `(A)`.
+fn priority_letter(word: &str) -> Option<char> {
+    let letter = word.strip_prefix('(')?.strip_suffix(')')?;
+    let mut chars = letter.chars();
+    match (chars.next(), chars.next()) {
+        (Some(letter), None) if letter.is_ascii_uppercase() => Some(letter),
+        _ => None,
+    }
+}
+
+fn parse_date(value: &str) -> anyhow::Result<NaiveDate> {
+    NaiveDate::parse_from_str(value, DATE).with_context(|| format!("invalid date '{value}'"))
+}
+
+/// Parses an hour like `14:00` or `14`. Minutes are dropped.
+fn parse_hour(value: &str) -> anyhow::Result<usize> {
+    let hour = value.split(':').next().unwrap_or_default();
+    match hour.parse() {
+        Ok(hour) if hour < 24 => Ok(hour),
+        _ => bail!("invalid hour '{value}'"),
+    }
+}
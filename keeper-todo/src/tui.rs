@@ -0,0 +1,590 @@
+//! A full-screen terminal UI, for getting around the days and changing tasks
+//! without typing out dates and ids.
+
+use std::io;
+
+use anyhow::Context;
+use chrono::{Days, NaiveDate, Timelike};
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::{Backend, CrosstermBackend},
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{List, ListItem, ListState, Paragraph},
+    Frame, Terminal,
+};
+
+use crate::{
+    cli::split_desc,
+    data::{past_due, Keeper, Priority, Task, TaskIndex, TaskStatus, ALL_DAY_HOUR},
+};
+
+const HELP: &str =
+    "q quit  j/k select  h/l day  t today  w week  space done  J/K/H/L move  a/A add  e edit  d delete";
+
+/// Runs the UI until it's quit. Returns what was changed, for the commit
+/// message.
+pub fn run(keeper: &mut Keeper) -> anyhow::Result<Vec<String>> {
+    enable_raw_mode().context("failed to enable raw mode")?;
+    execute!(io::stdout(), EnterAlternateScreen).context("failed to enter alternate screen")?;
+    let mut app = App::new(keeper);
+    let result = Terminal::new(CrosstermBackend::new(io::stdout()))
+        .context("failed to start terminal")
+        .and_then(|mut terminal| app.run(&mut terminal));
+    // Put the terminal back even if something went wrong
+    disable_raw_mode().context("failed to disable raw mode")?;
+    execute!(io::stdout(), LeaveAlternateScreen).context("failed to leave alternate screen")?;
+    result?;
+    Ok(app.changes)
+}
+
+/// Whether to show one day or seven.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum View {
+    Day,
+    Week,
+}
+
+/// What typed keys do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Mode {
+    Normal,
+    Input {
+        action: Input,
+        text: String,
+    },
+    /// Waiting for a `y` to delete the selected task.
+    Delete,
+}
+
+/// What the typed text is for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Input {
+    Add,
+    AddSubtask,
+    Edit,
+}
+
+/// A task on screen. `path` is its index in its hour, then subtask indices.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Row {
+    date: NaiveDate,
+    hour: usize,
+    path: Vec<usize>,
+    id: u64,
+}
+
+pub struct App<'a> {
+    keeper: &'a mut Keeper,
+    view: View,
+    /// The day shown, or the first of the seven.
+    date: NaiveDate,
+    /// An index into [`App::rows`].
+    selected: usize,
+    mode: Mode,
+    /// What the last key did, in place of the help.
+    message: String,
+    changes: Vec<String>,
+    quit: bool,
+}
+
+impl<'a> App<'a> {
+    /// Starts on today, at the first task from this hour on.
+    pub fn new(keeper: &'a mut Keeper) -> Self {
+        // Selections follow ids around
+        keeper.assign_ids();
+        let now = keeper.clock().now();
+        let mut app = Self {
+            keeper,
+            view: View::Day,
+            date: now.date_naive(),
+            selected: 0,
+            mode: Mode::Normal,
+            message: String::new(),
+            changes: Vec::new(),
+            quit: false,
+        };
+        let rows = app.rows();
+        app.selected = rows
+            .iter()
+            .position(|row| row.hour >= now.hour() as usize)
+            .unwrap_or(0);
+        app
+    }
+
+    /// What's been changed so far, like `mark 'standup' done`.
+    pub fn changes(&self) -> &[String] {
+        &self.changes
+    }
+
+    pub fn quit(&self) -> bool {
+        self.quit
+    }
+
+    fn run(&mut self, terminal: &mut Terminal<impl Backend>) -> anyhow::Result<()> {
+        while !self.quit {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read().context("failed to read key")? {
+                // Windows sends releases too
+                if key.kind == KeyEventKind::Press {
+                    self.handle(key);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn dates(&self) -> Vec<NaiveDate> {
+        let days = match self.view {
+            View::Day => 1,
+            View::Week => 7,
+        };
+        self.date.iter_days().take(days).collect()
+    }
+
+    fn rows(&self) -> Vec<Row> {
+        fn walk(
+            task: &Task,
+            date: NaiveDate,
+            hour: usize,
+            path: &mut Vec<usize>,
+            rows: &mut Vec<Row>,
+        ) {
+            rows.push(Row {
+                date,
+                hour,
+                path: path.clone(),
+                id: task.id(),
+            });
+            for (index, subtask) in task.subtasks().iter().enumerate() {
+                path.push(index);
+                walk(subtask, date, hour, path, rows);
+                path.pop();
+            }
+        }
+
+        let mut rows = Vec::new();
+        for date in self.dates() {
+            let Some(schedule) = self.keeper.days.get(&date) else {
+                continue;
+            };
+            for (hour, tasks) in &schedule.timeslots {
+                for (index, task) in tasks.iter().enumerate() {
+                    walk(task, date, *hour, &mut vec![index], &mut rows);
+                }
+            }
+        }
+        rows
+    }
+
+    fn selection(&self) -> Option<(Row, &Task)> {
+        let row = self.rows().into_iter().nth(self.selected)?;
+        let task = self.keeper.subtask(row.date, row.hour, &row.path)?;
+        Some((row, task))
+    }
+
+    /// Keeps the task with `id` selected after things have moved around.
+    fn select(&mut self, id: u64) {
+        if let Some(index) = self.rows().iter().position(|row| row.id == id) {
+            self.selected = index;
+        }
+    }
+
+    fn clamp_selection(&mut self) {
+        self.selected = self.selected.min(self.rows().len().saturating_sub(1));
+    }
+
+    fn change(&mut self, change: String) {
+        self.message = change.clone();
+        self.changes.push(change);
+    }
+
+    pub fn handle(&mut self, key: KeyEvent) {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            match self.mode {
+                Mode::Normal => self.quit = true,
+                _ => self.mode = Mode::Normal,
+            }
+            return;
+        }
+        match std::mem::replace(&mut self.mode, Mode::Normal) {
+            Mode::Normal => self.normal(key.code),
+            Mode::Input { action, mut text } => match key.code {
+                KeyCode::Enter => self.submit(action, &text),
+                KeyCode::Esc => self.message.clear(),
+                KeyCode::Backspace => {
+                    text.pop();
+                    self.mode = Mode::Input { action, text };
+                }
+                KeyCode::Char(c) => {
+                    text.push(c);
+                    self.mode = Mode::Input { action, text };
+                }
+                _ => self.mode = Mode::Input { action, text },
+            },
+            Mode::Delete => match key.code {
+                KeyCode::Char('y') => self.delete(),
+                _ => self.message = "kept it".to_string(),
+            },
+        }
+    }
+
+    fn normal(&mut self, code: KeyCode) {
+        self.message.clear();
+        let step = match self.view {
+            View::Day => 1,
+            View::Week => 7,
+        };
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.selected = (self.selected + 1).min(self.rows().len().saturating_sub(1))
+            }
+            KeyCode::Char('k') | KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Char('h') | KeyCode::Left => {
+                self.date = self.date - Days::new(step);
+                self.selected = 0;
+            }
+            KeyCode::Char('l') | KeyCode::Right => {
+                self.date = self.date + Days::new(step);
+                self.selected = 0;
+            }
+            KeyCode::Char('t') => {
+                self.date = self.keeper.clock().today();
+                self.selected = 0;
+            }
+            KeyCode::Char('w') => {
+                self.view = match self.view {
+                    View::Day => View::Week,
+                    View::Week => View::Day,
+                };
+                self.clamp_selection();
+            }
+            KeyCode::Char(' ') | KeyCode::Char('x') => self.toggle(),
+            KeyCode::Char('J') => self.shift(0, 1),
+            KeyCode::Char('K') => self.shift(0, -1),
+            KeyCode::Char('L') => self.shift(1, 0),
+            KeyCode::Char('H') => self.shift(-1, 0),
+            KeyCode::Char('a') => self.input(Input::Add, String::new()),
+            KeyCode::Char('A') => self.input(Input::AddSubtask, String::new()),
+            KeyCode::Char('e') => {
+                if let Some((_, task)) = self.selection() {
+                    let desc = task.desc().to_string();
+                    self.input(Input::Edit, desc);
+                }
+            }
+            KeyCode::Char('d') => {
+                if let Some((_, task)) = self.selection() {
+                    self.message = format!("delete '{}' and its subtasks? (y/n)", task.desc());
+                    self.mode = Mode::Delete;
+                }
+            }
+            _ => self.message = HELP.to_string(),
+        }
+    }
+
+    fn input(&mut self, action: Input, text: String) {
+        if action != Input::Add && self.selection().is_none() {
+            self.message = "no task selected".to_string();
+            return;
+        }
+        self.mode = Mode::Input { action, text };
+    }
+
+    /// Where `a` adds a task: the selected task's hour, or this hour today.
+    fn add_slot(&self) -> (NaiveDate, usize) {
+        if let Some((row, _)) = self.selection() {
+            return (row.date, row.hour);
+        }
+        let now = self.keeper.clock().now();
+        match self.date == now.date_naive() {
+            true => (self.date, now.hour() as usize),
+            false => (self.date, ALL_DAY_HOUR),
+        }
+    }
+
+    fn prompt(&self, action: Input) -> String {
+        match action {
+            Input::Add => {
+                let (date, hour) = self.add_slot();
+                format!(
+                    "add at {hour}:00 on {} (or start with an hour): ",
+                    date.format("%d %b")
+                )
+            }
+            Input::AddSubtask => "add subtask: ".to_string(),
+            Input::Edit => "edit: ".to_string(),
+        }
+    }
+
+    fn submit(&mut self, action: Input, text: &str) {
+        let now = self.keeper.clock().now();
+        let mut words = text.split_whitespace().peekable();
+        // Only a new task can go at a different hour
+        let hour = match action {
+            Input::Add => words
+                .peek()
+                .and_then(|word| word.trim_end_matches(":00").parse().ok())
+                .filter(|hour| *hour < 24),
+            _ => None,
+        };
+        if hour.is_some() {
+            words.next();
+        }
+        let (desc, priority, tags) = split_desc(&words.collect::<Vec<_>>().join(" "));
+        if desc.is_empty() {
+            self.message = "no description, so nothing changed".to_string();
+            return;
+        }
+
+        let id = match action {
+            Input::Add | Input::AddSubtask => {
+                let mut task = Task::with_priority(desc.clone(), priority.unwrap_or_default());
+                for tag in &tags {
+                    task.add_tag(tag);
+                }
+                task.touch(now);
+                if action == Input::Add {
+                    let (date, default_hour) = self.add_slot();
+                    let hour = hour.unwrap_or(default_hour);
+                    self.keeper.put(Some((date, hour)), task);
+                    self.change(format!(
+                        "add '{desc}' at {hour}:00 on {}",
+                        date.format("%d %b")
+                    ));
+                } else {
+                    let Some((row, _)) = self.selection() else {
+                        return;
+                    };
+                    let parent = self
+                        .keeper
+                        .subtask_mut(row.date, row.hour, &row.path)
+                        .unwrap();
+                    parent.touch(now);
+                    parent.add_subtask(task);
+                    let parent = parent.desc().to_string();
+                    self.change(format!("add '{desc}' under '{parent}'"));
+                }
+                // Everything else got its id when it was loaded
+                self.keeper.assign_ids().first().copied()
+            }
+            Input::Edit => {
+                let Some((row, _)) = self.selection() else {
+                    return;
+                };
+                let task = self
+                    .keeper
+                    .subtask_mut(row.date, row.hour, &row.path)
+                    .unwrap();
+                let old = task.desc().to_string();
+                task.set_desc(desc.clone());
+                if let Some(priority) = priority {
+                    task.set_priority(priority);
+                }
+                for tag in &tags {
+                    task.add_tag(tag);
+                }
+                task.touch(now);
+                self.change(format!("edit '{old}' to '{desc}'"));
+                Some(row.id)
+            }
+        };
+        self.keeper.order();
+        if let Some(id) = id {
+            self.select(id);
+        }
+    }
+
+    fn toggle(&mut self) {
+        let Some((row, task)) = self.selection() else {
+            return;
+        };
+        let now = self.keeper.clock().now();
+        let blockers: Vec<_> = self
+            .keeper
+            .blockers(task)
+            .iter()
+            .map(|blocker| format!("'{}'", blocker.desc()))
+            .collect();
+        let completed = task.completed();
+
+        let (index, rest) = row.path.split_first().unwrap();
+        let task = self.keeper.task_mut(row.date, row.hour, *index).unwrap();
+        match completed {
+            true => {
+                let desc = task.unmark_path(rest, now).unwrap();
+                self.change(format!("unmark '{desc}'"));
+            }
+            false => {
+                let desc = task.mark_path(rest, now).unwrap();
+                self.change(format!("mark '{desc}' done"));
+                if !blockers.is_empty() {
+                    self.message = format!("'{desc}' is still waiting on {}", blockers.join(", "));
+                }
+            }
+        }
+        self.keeper.order();
+        self.select(row.id);
+    }
+
+    /// Moves the selected task `days` days and `hours` hours.
+    fn shift(&mut self, days: i64, hours: i64) {
+        let Some((row, task)) = self.selection() else {
+            return;
+        };
+        let [index] = row.path[..] else {
+            self.message = "subtasks move with their parent".to_string();
+            return;
+        };
+        let desc = task.desc().to_string();
+        let hour = row.hour as i64 + hours;
+        if !(0..24).contains(&hour) {
+            self.message = "can't move past midnight".to_string();
+            return;
+        }
+        let hour = hour as usize;
+        let date = match days.is_negative() {
+            true => row.date - Days::new(days.unsigned_abs()),
+            false => row.date + Days::new(days as u64),
+        };
+        self.keeper.move_task(row.date, row.hour, index, date, hour);
+        self.change(format!(
+            "move '{desc}' to {hour}:00 on {}",
+            date.format("%d %b")
+        ));
+
+        // Follow it off the screen
+        let dates = self.dates();
+        if date < dates[0] {
+            self.date = date;
+        } else if date > dates[dates.len() - 1] {
+            self.date = date - Days::new(dates.len() as u64 - 1);
+        }
+        self.select(row.id);
+    }
+
+    fn delete(&mut self) {
+        let Some((row, _)) = self.selection() else {
+            return;
+        };
+        if let Some(task) = self.keeper.remove(row.date, row.hour, &row.path) {
+            self.change(format!("delete '{}'", task.desc()));
+        }
+        self.clamp_selection();
+    }
+
+    pub fn draw(&self, frame: &mut Frame) {
+        let [main, footer] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.size());
+        let now = self.keeper.clock().now();
+        let rows = self.rows();
+        let by_id = self.keeper.index();
+
+        let mut items = Vec::new();
+        let mut selected = None;
+        for (i, date) in self.dates().into_iter().enumerate() {
+            if i > 0 {
+                items.push(ListItem::new(""));
+            }
+            let mut heading = date.format("%a %d %b %Y").to_string();
+            if date == now.date_naive() {
+                heading.push_str(" (today)");
+            }
+            items.push(ListItem::new(Line::styled(
+                heading,
+                Style::new().add_modifier(Modifier::BOLD),
+            )));
+            let mut empty = true;
+            for (index, row) in rows.iter().enumerate().filter(|(_, row)| row.date == date) {
+                empty = false;
+                if index == self.selected {
+                    selected = Some(items.len());
+                }
+                let task = self.keeper.subtask(row.date, row.hour, &row.path).unwrap();
+                let slot_passed = past_due(row.date, row.hour, now);
+                items.push(ListItem::new(self.line(&by_id, row, task, slot_passed)));
+            }
+            if empty {
+                items.push(ListItem::new(Line::styled(
+                    "Empty",
+                    Style::new().fg(Color::DarkGray),
+                )));
+            }
+        }
+        let list = List::new(items)
+            .highlight_symbol("> ")
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        let mut state = ListState::default().with_selected(selected);
+        frame.render_stateful_widget(list, main, &mut state);
+
+        let footer_text = match &self.mode {
+            Mode::Input { action, text } => {
+                let prompt = self.prompt(*action);
+                let cursor = (prompt.chars().count() + text.chars().count()) as u16;
+                frame.set_cursor(footer.x + cursor.min(footer.width), footer.y);
+                format!("{prompt}{text}")
+            }
+            _ if self.message.is_empty() => HELP.to_string(),
+            _ => self.message.clone(),
+        };
+        frame.render_widget(Paragraph::new(footer_text), footer);
+    }
+
+    fn line(&self, by_id: &TaskIndex, row: &Row, task: &Task, slot_passed: bool) -> Line<'static> {
+        let now = self.keeper.clock().now();
+        let blocked = by_id.blocked(task);
+        let status = TaskStatus::of(task, task.overdue(slot_passed, now));
+        let color = match (status, blocked) {
+            (TaskStatus::Done, _) => Color::Green,
+            (_, true) => Color::Magenta,
+            (TaskStatus::Overdue, false) => Color::Red,
+            (TaskStatus::Pending, false) => Color::Reset,
+        };
+
+        // Subtasks line up under their parent's description
+        let prefix = match row.path.len() {
+            1 => format!("{:>2}:00 ", row.hour),
+            depth => format!("{:width$}", "", width = 6 + 4 * (depth - 1)),
+        };
+        let check = match task.completed() {
+            true => "[x] ",
+            false => "[ ] ",
+        };
+        let mut spans = vec![Span::raw(prefix), Span::raw(check)];
+        if task.priority() != Priority::Normal {
+            spans.push(Span::styled(
+                format!("{} ", task.priority().marker()),
+                Style::new().fg(Color::Yellow),
+            ));
+        }
+        spans.push(Span::styled(
+            task.desc().to_string(),
+            Style::new().fg(color),
+        ));
+        if let Some((done, total)) = task.subtask_progress() {
+            spans.push(Span::raw(format!(" [{done}/{total}]")));
+        }
+        if let Some(deadline) = task.deadline() {
+            let color = match status == TaskStatus::Overdue {
+                true => Color::Red,
+                false => Color::Yellow,
+            };
+            spans.push(Span::styled(
+                format!(" due {deadline}"),
+                Style::new().fg(color),
+            ));
+        }
+        for tag in task.tags() {
+            spans.push(Span::styled(
+                format!(" {tag}"),
+                Style::new().fg(Color::Cyan),
+            ));
+        }
+        Line::from(spans)
+    }
+}
@@ -0,0 +1,251 @@
+use std::{env, path::PathBuf, process};
+
+use anyhow::{ensure, Context};
+use keeper_util::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::render::MIN_SIZE;
+
+const HOME: &str = env!("HOME");
+// Used when there is nothing configured and nothing detected, e.g. on MacOS
+const SCREEN_HEIGHT: u32 = 956;
+const SCREEN_WIDTH: u32 = 1470;
+
+/// How the rendered images get onto the desktop.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy)]
+pub enum Backend {
+    /// The `wp.workflow` automator workflow. It sets every desktop to the same
+    /// picture, so only the first monitor's image is used.
+    #[default]
+    Automator,
+    /// MacOS System Events, one image per desktop in monitor order.
+    AppleScript,
+    /// `feh --bg-fill`, one image per screen in monitor order (X11).
+    Feh,
+    /// `swww img --outputs`, one image per output by name (Wayland).
+    Swww,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Monitor {
+    /// Output name as reported by `xrandr`/`wlr-randr`, e.g. `"DP-1"`.
+    #[serde(default)]
+    pub name: Option<String>,
+    pub width: u32,
+    pub height: u32,
+    /// Show this many days starting today instead of whatever was asked for.
+    #[serde(default)]
+    pub days: Option<usize>,
+}
+
+impl Monitor {
+    fn new(name: &str, width: u32, height: u32) -> Self {
+        Self {
+            name: Some(name.to_string()),
+            width,
+            height,
+            days: None,
+        }
+    }
+
+    /// The monitors to render for: the configured ones if there are any,
+    /// otherwise whatever we can detect. Monitors too small to render on are
+    /// skipped.
+    pub fn resolve(configured: &[Monitor]) -> Vec<Monitor> {
+        let configured = usable(configured.to_vec());
+        if !configured.is_empty() {
+            return configured;
+        }
+
+        let detected = if env::var_os("WAYLAND_DISPLAY").is_some() {
+            run("wlr-randr", &[]).map(|output| parse_wlr_randr(&output))
+        } else if env::var_os("DISPLAY").is_some() {
+            run("xrandr", &["--query"]).map(|output| parse_xrandr(&output))
+        } else {
+            None
+        };
+
+        match detected.map(usable) {
+            Some(monitors) if !monitors.is_empty() => monitors,
+            _ => vec![Monitor {
+                name: None,
+                width: SCREEN_WIDTH,
+                height: SCREEN_HEIGHT,
+                days: None,
+            }],
+        }
+    }
+}
+
+fn usable(monitors: Vec<Monitor>) -> Vec<Monitor> {
+    monitors
+        .into_iter()
+        .filter(|monitor| {
+            let usable = monitor.width >= MIN_SIZE.0 && monitor.height >= MIN_SIZE.1;
+            if !usable {
+                warn!(
+                    "skipping monitor {} at {}x{}, it needs to be at least {}x{}",
+                    monitor.name.as_deref().unwrap_or("without a name"),
+                    monitor.width,
+                    monitor.height,
+                    MIN_SIZE.0,
+                    MIN_SIZE.1
+                );
+            }
+            usable
+        })
+        .collect()
+}
+
+fn run(program: &str, args: &[&str]) -> Option<String> {
+    let output = process::Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout).ok()
+}
+
+/// Parses the connected outputs out of `xrandr --query`, e.g.
+///
+/// ```text
+/// DP-1 connected primary 2560x1440+0+0 (normal left inverted right) 597mm x 336mm
+/// HDMI-1 disconnected (normal left inverted right x axis y axis)
+/// ```
+pub fn parse_xrandr(output: &str) -> Vec<Monitor> {
+    output
+        .lines()
+        .filter(|line| !line.starts_with(char::is_whitespace))
+        .filter_map(|line| {
+            let mut words = line.split_whitespace();
+            let name = words.next()?;
+            if words.next()? != "connected" {
+                return None;
+            }
+            // Outputs that are connected but turned off have no geometry
+            let geometry = words.find(|word| word.contains('x') && word.contains('+'))?;
+            let (size, _) = geometry.split_once('+')?;
+            let (width, height) = size.split_once('x')?;
+            Some(Monitor::new(
+                name,
+                width.parse().ok()?,
+                height.parse().ok()?,
+            ))
+        })
+        .collect()
+}
+
+/// Parses the enabled outputs out of `wlr-randr`, e.g.
+///
+/// ```text
+/// DP-1 "Dell Inc. DELL U2720Q"
+///   Enabled: yes
+///   Modes:
+///     3840x2160 px, 59.997002 Hz (preferred, current)
+///   Transform: 90
+/// ```
+pub fn parse_wlr_randr(output: &str) -> Vec<Monitor> {
+    let mut monitors = Vec::new();
+    let mut current: Option<(Monitor, bool)> = None;
+    for line in output.lines() {
+        if !line.starts_with(char::is_whitespace) {
+            monitors.extend(
+                current
+                    .take()
+                    .filter(|(_, enabled)| *enabled)
+                    .map(|(m, _)| m),
+            );
+            let Some(name) = line.split_whitespace().next() else {
+                continue;
+            };
+            current = Some((Monitor::new(name, 0, 0), true));
+            continue;
+        }
+
+        let Some((monitor, enabled)) = current.as_mut() else {
+            continue;
+        };
+        let line = line.trim();
+        if let Some(value) = line.strip_prefix("Enabled:") {
+            *enabled = value.trim() == "yes";
+        } else if let Some(value) = line.strip_prefix("Transform:") {
+            // Portrait monitors report their mode in landscape
+            if matches!(value.trim(), "90" | "270" | "flipped-90" | "flipped-270") {
+                (monitor.width, monitor.height) = (monitor.height, monitor.width);
+            }
+        } else if line.contains("current") {
+            let Some((width, height)) = line
+                .split_whitespace()
+                .next()
+                .and_then(|mode| mode.split_once('x'))
+            else {
+                continue;
+            };
+            if let (Ok(width), Ok(height)) = (width.parse(), height.parse()) {
+                (monitor.width, monitor.height) = (width, height);
+            }
+        }
+    }
+    monitors.extend(current.filter(|(_, enabled)| *enabled).map(|(m, _)| m));
+    monitors.retain(|monitor| monitor.width > 0 && monitor.height > 0);
+    monitors
+}
+
+impl Backend {
+    /// Sets each monitor's wallpaper to its image.
+    pub fn apply(&self, wallpapers: &[(Monitor, PathBuf)]) -> anyhow::Result<()> {
+        let Some((_, first)) = wallpapers.first() else {
+            return Ok(());
+        };
+
+        match self {
+            Backend::Automator => {
+                process::Command::new("automator")
+                    .args([
+                        "-i".as_ref(),
+                        first.as_os_str(),
+                        format!("{HOME}/.local/share/keeper/wp.workflow").as_ref(),
+                    ])
+                    .output()
+                    .context("automator workflow failed")?;
+            }
+            Backend::AppleScript => {
+                for (i, (_, path)) in wallpapers.iter().enumerate() {
+                    let script = format!(
+                        "tell application \"System Events\" to set picture of desktop {} to POSIX file {:?}",
+                        i + 1,
+                        path
+                    );
+                    let status = process::Command::new("osascript")
+                        .args(["-e", &script])
+                        .status()
+                        .context("failed to run osascript")?;
+                    ensure!(
+                        status.success(),
+                        "osascript failed to set desktop {}",
+                        i + 1
+                    );
+                }
+            }
+            Backend::Feh => {
+                let status = process::Command::new("feh")
+                    .args(["--no-fehbg", "--bg-fill"])
+                    .args(wallpapers.iter().map(|(_, path)| path))
+                    .status()
+                    .context("failed to run feh")?;
+                ensure!(status.success(), "feh failed to set wallpapers");
+            }
+            Backend::Swww => {
+                for (monitor, path) in wallpapers {
+                    let mut command = process::Command::new("swww");
+                    command.arg("img").arg(path);
+                    if let Some(name) = &monitor.name {
+                        command.args(["--outputs", name]);
+                    }
+                    let status = command.status().context("failed to run swww")?;
+                    ensure!(status.success(), "swww failed to set wallpaper {path:?}");
+                }
+            }
+        }
+        Ok(())
+    }
+}
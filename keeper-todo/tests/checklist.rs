@@ -0,0 +1,137 @@
+mod common;
+
+use common::{assert_text_snapshot, at, date, mixed, utc};
+use keeper_todo::{
+    checklist::{self, Flavor},
+    data::{Keeper, Priority, Task},
+};
+use keeper_util::clock::Clock;
+
+fn empty() -> Keeper {
+    utc();
+    let mut keeper = Keeper::default();
+    keeper.set_clock(Clock::Fixed(at("2024-02-01T13:30:00Z")));
+    keeper
+}
+
+fn subtask<'a>(task: &'a Task, desc: &str) -> &'a Task {
+    task.subtasks().iter().find(|t| t.desc() == desc).unwrap()
+}
+
+fn days() -> (Option<chrono::NaiveDate>, Option<chrono::NaiveDate>) {
+    (Some(date("2024-02-01")), Some(date("2024-02-03")))
+}
+
+#[test]
+fn export_markdown() {
+    let keeper = mixed();
+    let (from, to) = days();
+    assert_text_snapshot(
+        "checklist_md",
+        &checklist::export(&keeper, Flavor::Markdown, from, to),
+    );
+}
+
+#[test]
+fn export_org() {
+    let keeper = mixed();
+    let (from, to) = days();
+    assert_text_snapshot(
+        "checklist_org",
+        &checklist::export(&keeper, Flavor::Org, from, to),
+    );
+}
+
+#[test]
+fn checklist_round_trip() {
+    let keeper = mixed();
+    for flavor in [Flavor::Markdown, Flavor::Org] {
+        let exported = checklist::export(&keeper, flavor, None, None);
+        let mut imported = empty();
+        let now = imported.clock().now();
+        let tasks = checklist::import(&exported, flavor, date("2024-01-01"), now).unwrap();
+        assert_eq!(imported.import(tasks), (10, 0));
+        assert_eq!(checklist::export(&imported, flavor, None, None), exported);
+    }
+}
+
+#[test]
+fn checklist_syncs() {
+    let mut keeper = mixed();
+    let today = date("2024-02-01");
+    let exported = checklist::export(&keeper, Flavor::Markdown, Some(today), Some(today));
+
+    let edited = exported
+        .replace("- [ ] 17:00 gym", "- [x] 17:00 gym")
+        .replace("- [ ] 14:00 write docs", "- [ ] 10:00 write docs")
+        .replace("- [ ] announce", "- [x] announce")
+        .replace("- [ ] bump version", "- [ ] bump version\n    - [ ] tag rc");
+    let tasks = checklist::import(&edited, Flavor::Markdown, today, keeper.clock().now()).unwrap();
+    assert_eq!(keeper.import(tasks), (0, 9));
+
+    let gym = keeper.task(today, 17, 0).unwrap();
+    assert!(gym.completed());
+    assert_eq!(gym.completed_at(), Some(at("2024-02-01T13:30:00Z")));
+    // Moved rather than added again
+    assert!(!keeper.days[&today].timeslots.contains_key(&14));
+    assert_eq!(keeper.task(today, 10, 0).unwrap().desc(), "write docs");
+
+    let release = keeper.task(today, 15, 0).unwrap();
+    let tag = subtask(release, "tag release");
+    assert!(subtask(tag, "announce").completed());
+    assert!(subtask(tag, "push tag").completed());
+    let bump = subtask(release, "bump version");
+    assert_eq!(bump.subtasks()[0].desc(), "tag rc");
+    // Notes the checklist doesn't have stay put
+    let trip = keeper.task(today, 16, 0).unwrap();
+    assert_eq!(trip.details().links, ["https://example.com/flights"]);
+}
+
+#[test]
+fn import_notes() {
+    let mut keeper = empty();
+    let note = "\
+# Standup notes
+
+Some thoughts, and a list that isn't a checklist:
+- not a task
+
+- [ ] 9:30 email triage !high +work
+- [ ] call plumber @phone due:2024-02-03 duetime:17:00
+
+## 02 Feb 2024
+
+* [X] 10:00 dentist
+  * [ ] floss
+- [-] 1:00 late night
+";
+    let now = keeper.clock().now();
+    let tasks = checklist::import(note, Flavor::Markdown, date("2024-02-01"), now).unwrap();
+    assert_eq!(keeper.import(tasks), (4, 0));
+
+    let triage = keeper.task(date("2024-02-01"), 9, 0).unwrap();
+    assert_eq!(triage.desc(), "email triage");
+    assert_eq!(triage.priority(), Priority::High);
+    assert_eq!(triage.tags(), ["+work"]);
+    // Items without an hour go where all-day tasks do
+    let plumber = keeper.task(date("2024-02-01"), 9, 1).unwrap();
+    assert_eq!(plumber.desc(), "call plumber");
+    let deadline = plumber.deadline().unwrap();
+    assert_eq!(
+        (deadline.date, deadline.hour),
+        (date("2024-02-03"), Some(17))
+    );
+
+    let dentist = keeper.task(date("2024-02-02"), 10, 0).unwrap();
+    assert!(dentist.completed());
+    // Ticking the parent doesn't tick what's left underneath it
+    assert!(!dentist.subtasks()[0].completed());
+    // Not done before now, even though it's ticked on a later day
+    assert_eq!(dentist.completed_at(), Some(now));
+    assert!(!keeper.task(date("2024-02-02"), 1, 0).unwrap().completed());
+
+    let org = "* <2024-02-05 Mon>\n- [ ] 8:00 run\n";
+    let tasks = checklist::import(org, Flavor::Org, date("2024-02-01"), now).unwrap();
+    assert_eq!(keeper.import(tasks), (1, 0));
+    assert_eq!(keeper.task(date("2024-02-05"), 8, 0).unwrap().desc(), "run");
+}
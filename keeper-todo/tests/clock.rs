@@ -0,0 +1,138 @@
+use std::env;
+
+use chrono::{DateTime, Local, NaiveDate};
+use keeper_todo::{
+    cli::ShowSet,
+    data::{past_due, ColorStyle, Keeper, KeeperDisplay, Task},
+    filter::Filter,
+};
+use keeper_util::{
+    clock::{parse_now, Clock},
+    parse_date,
+};
+
+/// Pins the local timezone to one with DST. Every test in this file sets the
+/// same zone, so it doesn't matter that they share a process.
+fn new_york() {
+    env::set_var("TZ", "America/New_York");
+}
+
+fn at(timestamp: &str) -> DateTime<Local> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .unwrap()
+        .with_timezone(&Local)
+}
+
+fn date(s: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+}
+
+#[test]
+fn past_due_after_last_second_of_hour() {
+    new_york();
+    let day = date("2024-02-01");
+    assert!(!past_due(day, 10, at("2024-02-01T10:00:00-05:00")));
+    assert!(!past_due(day, 10, at("2024-02-01T10:59:59-05:00")));
+    assert!(past_due(day, 10, at("2024-02-01T10:59:59.001-05:00")));
+    assert!(past_due(day, 10, at("2024-02-01T11:00:00-05:00")));
+    assert!(!past_due(day, 11, at("2024-02-01T11:00:00-05:00")));
+}
+
+#[test]
+fn past_due_last_hour_of_day() {
+    new_york();
+    let day = date("2024-02-01");
+    assert!(!past_due(day, 23, at("2024-02-01T23:59:59-05:00")));
+    assert!(past_due(day, 23, at("2024-02-02T00:00:00-05:00")));
+}
+
+#[test]
+fn past_due_when_clocks_go_back() {
+    new_york();
+    // 01:00-01:59 happens twice on 3 Nov 2024, first in EDT then in EST
+    let day = date("2024-11-03");
+    assert!(!past_due(day, 1, at("2024-11-03T01:59:59.500-04:00")));
+    assert!(!past_due(day, 1, at("2024-11-03T01:30:00-05:00")));
+    assert!(past_due(day, 1, at("2024-11-03T02:00:00-05:00")));
+    assert!(!past_due(day, 2, at("2024-11-03T02:00:00-05:00")));
+}
+
+#[test]
+fn past_due_when_clocks_go_forward() {
+    new_york();
+    // 02:00-02:59 never happens on 10 Mar 2024
+    let day = date("2024-03-10");
+    assert!(!past_due(day, 1, at("2024-03-10T01:30:00-05:00")));
+    assert!(!past_due(day, 2, at("2024-03-10T01:30:00-05:00")));
+    assert!(past_due(day, 1, at("2024-03-10T03:00:00-04:00")));
+    assert!(past_due(day, 2, at("2024-03-10T03:00:00-04:00")));
+    assert!(!past_due(day, 3, at("2024-03-10T03:00:00-04:00")));
+}
+
+#[test]
+fn fixed_clock_resolves_relative_dates() {
+    new_york();
+    let clock = Clock::Fixed(at("2024-02-29T23:30:00-05:00"));
+    assert_eq!(parse_date("today", clock), date("2024-02-29"));
+    assert_eq!(parse_date("tomorrow", clock), date("2024-03-01"));
+    assert_eq!(parse_date("yesterday", clock), date("2024-02-28"));
+    assert_eq!(parse_date("01-03-2024", clock), date("2024-03-01"));
+}
+
+#[test]
+fn keeper_now_formats() {
+    new_york();
+    let expected = at("2024-02-01T14:30:00-05:00");
+    assert_eq!(parse_now("2024-02-01T14:30:00-05:00"), Some(expected));
+    assert_eq!(parse_now("2024-02-01T19:30:00Z"), Some(expected));
+    assert_eq!(parse_now("2024-02-01 14:30"), Some(expected));
+    assert_eq!(parse_now("2024-02-01T14:30:00"), Some(expected));
+    assert_eq!(parse_now("yesterday-ish"), None);
+}
+
+#[test]
+fn display_colors_follow_clock() {
+    new_york();
+    let day = date("2024-02-01");
+    let mut keeper = Keeper::default();
+    for hour in [9, 10] {
+        keeper
+            .days
+            .entry(day)
+            .or_default()
+            .timeslots
+            .entry(hour)
+            .or_default()
+            .push(Task::new(format!("task at {hour}")));
+    }
+
+    keeper.set_clock(Clock::Fixed(at("2024-02-01T10:15:00-05:00")));
+    let shown = KeeperDisplay::new(
+        &keeper,
+        ShowSet::Date(day),
+        Filter::default(),
+        ColorStyle::Color,
+    )
+    .to_string();
+    assert!(
+        shown.contains("\x1b[0;31m[9]"),
+        "9 should be overdue: {shown:?}"
+    );
+    assert!(
+        shown.contains("\x1b[0;34m[10]"),
+        "10 should be pending: {shown:?}"
+    );
+
+    keeper.set_clock(Clock::Fixed(at("2024-02-01T11:00:00-05:00")));
+    let shown = KeeperDisplay::new(
+        &keeper,
+        ShowSet::Date(day),
+        Filter::default(),
+        ColorStyle::Color,
+    )
+    .to_string();
+    assert!(
+        shown.contains("\x1b[0;31m[10]"),
+        "10 should be overdue: {shown:?}"
+    );
+}
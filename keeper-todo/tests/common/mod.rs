@@ -0,0 +1,433 @@
+//! Fixtures and snapshot helpers shared by the integration tests.
+//!
+//! Snapshots live in `tests/snapshots`. Set `KEEPER_BLESS=1` to record new ones
+//! or re-record all of them after an intentional change. Without it, a missing
+//! snapshot is a failure, so a checkout without them can't pass by accident.
+
+#![allow(dead_code)]
+
+use std::{env, fs, path::PathBuf};
+
+use chrono::{DateTime, Local, NaiveDate};
+use image::{Rgb, RgbImage};
+use keeper_todo::{
+    cli::ShowSet,
+    data::{ColorStyle, Deadline, Keeper, KeeperDisplay, Priority, Task},
+    details::{ChecklistItem, Details},
+    filter::Filter,
+    font::Fonts,
+};
+use keeper_util::clock::Clock;
+
+/// A pixel counts as different if its (redmean weighted) color distance from
+/// the reference is above this. Out of a maximum of about 765.
+const PIXEL_TOLERANCE: f64 = 24.0;
+/// A render matches if at most this fraction of its pixels are different, which
+/// absorbs antialiasing differences between platforms.
+const IMAGE_TOLERANCE: f64 = 0.001;
+
+/// Pins the local timezone so that fixtures render the same everywhere.
+pub fn utc() {
+    env::set_var("TZ", "UTC");
+}
+
+pub fn at(timestamp: &str) -> DateTime<Local> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .unwrap()
+        .with_timezone(&Local)
+}
+
+pub fn date(s: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+}
+
+/// Tasks in fixtures get done three quarters of the way through their hour.
+pub fn done_at(date: NaiveDate, hour: usize) -> DateTime<Local> {
+    date.and_hms_opt(hour as u32, 45, 0)
+        .unwrap()
+        .and_local_timezone(Local)
+        .unwrap()
+}
+
+pub fn add(keeper: &mut Keeper, date: NaiveDate, hour: usize, desc: &str, done: bool) {
+    let mut task = Task::new(desc.to_string());
+    if done {
+        task.mark_complete(done_at(date, hour));
+    }
+    push(keeper, date, hour, task);
+}
+
+pub fn push(keeper: &mut Keeper, date: NaiveDate, hour: usize, task: Task) {
+    keeper
+        .days
+        .entry(date)
+        .or_default()
+        .timeslots
+        .entry(hour)
+        .or_default()
+        .push(task);
+}
+
+/// An ordinary day, looked at in the early afternoon.
+pub fn workday() -> Keeper {
+    utc();
+    let today = date("2024-02-01");
+    let tomorrow = date("2024-02-02");
+    let mut keeper = Keeper::default();
+    add(&mut keeper, today, 9, "standup", true);
+    add(&mut keeper, today, 9, "email triage", false);
+    add(&mut keeper, today, 11, "review PR", true);
+    add(&mut keeper, today, 13, "lunch", true);
+    add(&mut keeper, today, 14, "write docs", false);
+    add(&mut keeper, today, 17, "gym", false);
+    add(&mut keeper, tomorrow, 10, "dentist", false);
+    keeper.set_clock(Clock::Fixed(at("2024-02-01T13:30:00Z")));
+    keeper
+}
+
+/// A day with descriptions far too long for one line.
+pub fn wordy() -> Keeper {
+    let mut keeper = workday();
+    let today = date("2024-02-01");
+    add(
+        &mut keeper,
+        today,
+        15,
+        "prepare the quarterly planning document, including headcount, budget, the \
+         roadmap for the next two quarters and a retrospective on the last one",
+        false,
+    );
+    add(
+        &mut keeper,
+        today,
+        16,
+        "https://example.com/a/very/long/url/without/any/spaces/that/has/to/be/split/somewhere/in/the/middle",
+        false,
+    );
+    keeper
+}
+
+/// A day with more tasks than fit on the screen at the minimum font size.
+pub fn packed() -> Keeper {
+    let mut keeper = workday();
+    let today = date("2024-02-01");
+    for hour in 0..24 {
+        for i in 0..4 {
+            add(
+                &mut keeper,
+                today,
+                hour,
+                &format!("task {i} at {hour} with a description that goes on for a while"),
+                hour < 12,
+            );
+        }
+    }
+    keeper
+}
+
+/// A couple of days with `+project` and `@context` tags.
+pub fn tagged() -> Keeper {
+    let mut keeper = workday();
+    let today = date("2024-02-01");
+    let tomorrow = date("2024-02-02");
+    let tasks = [
+        (today, 10, "call the bank", vec!["+home", "@phone"]),
+        (today, 15, "fix login bug", vec!["+work"]),
+        (today, 15, "ping design", vec!["+work", "@phone"]),
+        (today, 18, "laundry", vec!["+home"]),
+        (tomorrow, 9, "sprint planning", vec!["+work", "@office"]),
+    ];
+    for (date, hour, desc, tags) in tasks {
+        let mut task = Task::new(desc.to_string());
+        for tag in tags {
+            task.add_tag(tag);
+        }
+        push(&mut keeper, date, hour, task);
+    }
+    keeper
+}
+
+/// A release with nested steps, some of them done.
+pub fn nested() -> Keeper {
+    let mut keeper = workday();
+    let today = date("2024-02-01");
+    let task = |desc: &str| Task::new(desc.to_string());
+
+    let mut tag = task("tag release");
+    tag.add_subtask(task("push tag"));
+    tag.add_subtask(task("announce"));
+    let mut release = task("ship release");
+    release.add_subtask(task("write changelog"));
+    release.add_subtask(task("bump version"));
+    release.add_subtask(tag);
+    release.mark_path(&[0], done_at(today, 14));
+    release.mark_path(&[2, 0], done_at(today, 15));
+    push(&mut keeper, today, 15, release);
+    keeper
+}
+
+/// A deploy waiting on a review that hasn't happened yet.
+pub fn blocked() -> Keeper {
+    let mut keeper = workday();
+    let today = date("2024-02-01");
+    add(&mut keeper, today, 15, "review release", false);
+    add(&mut keeper, today, 16, "deploy", false);
+    add(&mut keeper, today, 16, "update status page", false);
+    keeper.assign_ids();
+    let review = keeper.task(today, 15, 0).unwrap().id();
+    keeper
+        .task_mut(today, 16, 0)
+        .unwrap()
+        .add_dependency(review);
+    keeper
+}
+
+/// Some ideas that haven't been scheduled yet.
+pub fn inboxed() -> Keeper {
+    let mut keeper = workday();
+    let ideas = [
+        ("learn the cello", Priority::Low, vec![]),
+        ("renew passport", Priority::High, vec!["+home"]),
+        (
+            "write a blog post about keeper",
+            Priority::Normal,
+            vec!["+blog"],
+        ),
+    ];
+    for (desc, priority, tags) in ideas {
+        let mut task = Task::with_priority(desc.to_string(), priority);
+        for tag in tags {
+            task.add_tag(tag);
+        }
+        keeper.inbox.push(task);
+    }
+    let mut done = Task::new("fix bike".to_string());
+    done.mark_complete(done_at(date("2024-01-30"), 18));
+    keeper.inbox.push(done);
+    keeper.order();
+    keeper
+}
+
+/// Tasks with deadlines, some met and some missed.
+pub fn deadlined() -> Keeper {
+    let mut keeper = workday();
+    let today = date("2024-02-01");
+    let tomorrow = date("2024-02-02");
+    let tasks = [
+        (today, 10, "draft report", date("2024-02-02"), Some(12)),
+        (today, 15, "file expenses", today, Some(12)),
+        (today, 16, "book venue", date("2024-02-05"), None),
+        (today, 17, "plan offsite", date("2024-03-01"), None),
+        (tomorrow, 9, "submit grant", today, None),
+    ];
+    for (date, hour, desc, due, due_hour) in tasks {
+        let mut task = Task::new(desc.to_string());
+        task.set_deadline(Some(Deadline {
+            date: due,
+            hour: due_hour,
+        }));
+        push(&mut keeper, date, hour, task);
+    }
+    let mut passport = Task::new("renew passport".to_string());
+    passport.set_deadline(Some(Deadline {
+        date: date("2024-02-06"),
+        hour: None,
+    }));
+    keeper.inbox.push(passport);
+    keeper
+}
+
+/// A bit of everything, for trying out import and export. Timestamps are
+/// filled in like they are when keeper runs.
+pub fn mixed() -> Keeper {
+    let mut keeper = nested();
+    let today = date("2024-02-01");
+    let mut trip = Task::with_priority("plan trip; book, pack".to_string(), Priority::High);
+    trip.add_tag("+home");
+    trip.add_tag("@laptop");
+    trip.set_details(Details {
+        note: "Aim for March.\nAsk Sam, about the car.".to_string(),
+        links: vec!["https://example.com/flights".to_string()],
+        checklist: vec![ChecklistItem {
+            done: true,
+            text: "pick dates".to_string(),
+        }],
+    });
+    trip.set_deadline(Some(Deadline {
+        date: date("2024-02-02"),
+        hour: Some(12),
+    }));
+    push(&mut keeper, today, 16, trip);
+    let mut chores = Task::with_priority("chores".to_string(), Priority::Low);
+    chores.set_deadline(Some(Deadline {
+        date: date("2024-02-03"),
+        hour: None,
+    }));
+    push(&mut keeper, today, 18, chores);
+    keeper.inbox.push(Task::with_priority(
+        "learn the cello, someday".to_string(),
+        Priority::Urgent,
+    ));
+    keeper.backfill_timestamps();
+    keeper.assign_ids();
+    keeper.order();
+    keeper
+}
+
+/// [`mixed`] with chores waiting on the trip, so the composite snapshots show
+/// every kind of decoration at once.
+pub fn busy() -> Keeper {
+    let mut keeper = mixed();
+    let today = date("2024-02-01");
+    let trip = keeper.task(today, 16, 0).unwrap().id();
+    keeper.task_mut(today, 18, 0).unwrap().add_dependency(trip);
+    keeper
+}
+
+/// The two weeks leading up to [`workday`], with a few tasks put off.
+pub fn fortnight() -> Keeper {
+    let mut keeper = workday();
+    let start = date("2024-01-19");
+    let days = [
+        (2, 2),
+        (0, 0),
+        (0, 0),
+        (4, 4),
+        (3, 2),
+        (2, 2),
+        (3, 3),
+        (1, 1),
+        (4, 3),
+        (2, 2),
+        (3, 3),
+        (0, 0),
+        (2, 2),
+    ];
+    for (offset, (planned, done)) in days.into_iter().enumerate() {
+        let day = start + chrono::Days::new(offset as u64);
+        for i in 0..planned {
+            let hour = 9 + (i % 3) * 3;
+            add(&mut keeper, day, hour, &format!("task {i}"), i < done);
+        }
+    }
+    let mut docs = Task::new("write docs".to_string());
+    for _ in 0..3 {
+        docs.defer();
+    }
+    push(&mut keeper, date("2024-01-30"), 17, docs);
+    let mut taxes = Task::new("file taxes".to_string());
+    taxes.defer();
+    push(&mut keeper, date("2024-01-30"), 18, taxes);
+    keeper
+}
+
+/// What `show` prints for `set`, without colors so it's easy to match.
+pub fn shown(keeper: &Keeper, set: ShowSet, verbose: bool) -> String {
+    KeeperDisplay::new(keeper, set, Filter::default(), ColorStyle::NoColor)
+        .verbose(verbose)
+        .to_string()
+}
+
+/// DejaVu Sans Mono from `tests/fonts`, so that neither the embedded font nor
+/// installed ones change the reference images.
+pub fn fonts() -> Fonts {
+    Fonts::from_file(&font_path()).unwrap()
+}
+
+pub fn font_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fonts")
+        .join("DejaVuSansMono.ttf")
+}
+
+fn snapshot_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("snapshots")
+        .join(name)
+}
+
+/// Records `actual` as the snapshot if we're blessing, and returns whether it
+/// did. Panics if there is no snapshot to compare against otherwise.
+fn record(path: &PathBuf, write: impl FnOnce(&PathBuf)) -> bool {
+    if env::var_os("KEEPER_BLESS").is_none() {
+        assert!(
+            path.exists(),
+            "no snapshot at {path:?}, rerun with KEEPER_BLESS=1 to record it"
+        );
+        return false;
+    }
+    write(path);
+    eprintln!("recorded snapshot {path:?}");
+    true
+}
+
+pub fn assert_text_snapshot(name: &str, actual: &str) {
+    let path = snapshot_path(&format!("{name}.txt"));
+    if record(&path, |path| fs::write(path, actual).unwrap()) {
+        return;
+    }
+
+    let expected = fs::read_to_string(&path).unwrap();
+    assert_eq!(
+        actual, expected,
+        "{name} doesn't match {path:?}, rerun with KEEPER_BLESS=1 if this is intended"
+    );
+}
+
+/// Perceived distance between two colors, see
+/// https://www.compuphase.com/cmetric.htm
+fn distance(Rgb(a): &Rgb<u8>, Rgb(b): &Rgb<u8>) -> f64 {
+    let redmean = (a[0] as f64 + b[0] as f64) / 2.0;
+    let [dr, dg, db] = [0, 1, 2].map(|i| a[i] as f64 - b[i] as f64);
+    ((2.0 + redmean / 256.0) * dr * dr
+        + 4.0 * dg * dg
+        + (2.0 + (255.0 - redmean) / 256.0) * db * db)
+        .sqrt()
+}
+
+pub fn assert_image_snapshot(name: &str, actual: &RgbImage) {
+    let path = snapshot_path(&format!("{name}.png"));
+    if record(&path, |path| actual.save(path).unwrap()) {
+        return;
+    }
+
+    let expected = image::open(&path).unwrap().to_rgb8();
+    let failures = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("snapshots");
+    fs::create_dir_all(&failures).unwrap();
+    let actual_path = failures.join(format!("{name}.actual.png"));
+
+    if expected.dimensions() != actual.dimensions() {
+        actual.save(&actual_path).unwrap();
+        panic!(
+            "{name} is {:?} but {path:?} is {:?}, see {actual_path:?}",
+            actual.dimensions(),
+            expected.dimensions()
+        );
+    }
+
+    // Different pixels are drawn in red over a faded copy of the reference
+    let mut diff = RgbImage::new(actual.width(), actual.height());
+    let mut different = 0;
+    for ((x, y, a), e) in actual.enumerate_pixels().zip(expected.pixels()) {
+        if distance(a, e) > PIXEL_TOLERANCE {
+            different += 1;
+            diff.put_pixel(x, y, Rgb([0xff, 0, 0]));
+        } else {
+            diff.put_pixel(x, y, Rgb(e.0.map(|c| c / 4)));
+        }
+    }
+
+    let fraction = different as f64 / (actual.width() * actual.height()) as f64;
+    if fraction > IMAGE_TOLERANCE {
+        let diff_path = failures.join(format!("{name}.diff.png"));
+        actual.save(&actual_path).unwrap();
+        diff.save(&diff_path).unwrap();
+        panic!(
+            "{name} differs from {path:?} in {:.3}% of pixels, see {actual_path:?} and \
+             {diff_path:?}, rerun with KEEPER_BLESS=1 if this is intended",
+            fraction * 100.0
+        );
+    }
+}
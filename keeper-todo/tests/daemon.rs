@@ -0,0 +1,22 @@
+mod common;
+
+use common::{at, utc};
+use keeper_todo::daemon::{next_minute, next_refresh};
+
+#[test]
+fn wakes_for_the_now_marker() {
+    utc();
+    // The marker shows 13:30, so it's stale at 13:31
+    let now = at("2024-02-01T13:30:42.5Z");
+    assert_eq!(next_minute(now), at("2024-02-01T13:31:00Z"));
+    assert_eq!(
+        next_minute(at("2024-02-01T13:31:00Z")),
+        at("2024-02-01T13:32:00Z")
+    );
+    assert_eq!(
+        next_minute(at("2024-02-01T23:59:30Z")),
+        at("2024-02-02T00:00:00Z")
+    );
+    // The status bar only cares about slots going overdue
+    assert_eq!(next_refresh(now), at("2024-02-01T14:00:00Z"));
+}
@@ -0,0 +1,96 @@
+mod common;
+
+use common::{at, date, deadlined, shown, utc};
+use keeper_todo::{cli::ShowSet, data::Deadline, filter::Filter};
+
+#[test]
+fn overdue_by_deadline_not_slot() {
+    let keeper = deadlined();
+    let now = at("2024-02-01T13:30:00Z");
+    let today = date("2024-02-01");
+    // 10:00 has passed, but it isn't due until tomorrow
+    let report = keeper.task(today, 10, 0).unwrap();
+    assert!(!report.overdue(true, now));
+    // 15:00 is still to come, but it was due at noon
+    let expenses = keeper.task(today, 15, 0).unwrap();
+    assert!(expenses.overdue(false, now));
+    // Without a deadline, the slot decides
+    let email = keeper.task(today, 9, 0).unwrap();
+    assert!(email.overdue(true, now));
+    assert!(!email.overdue(false, now));
+}
+
+#[test]
+fn deadline_lasts_until_end_of_hour_or_day() {
+    utc();
+    let deadline = Deadline {
+        date: date("2024-02-01"),
+        hour: None,
+    };
+    assert!(!deadline.passed(at("2024-02-01T23:59:59Z")));
+    assert!(deadline.passed(at("2024-02-02T00:00:00Z")));
+    let deadline = Deadline {
+        hour: Some(12),
+        ..deadline
+    };
+    assert!(!deadline.passed(at("2024-02-01T12:30:00Z")));
+    assert!(deadline.passed(at("2024-02-01T13:00:00Z")));
+
+    assert!(!deadline.missed_by(date("2024-02-01"), 12));
+    assert!(deadline.missed_by(date("2024-02-01"), 13));
+    assert!(deadline.missed_by(date("2024-02-02"), 9));
+}
+
+#[test]
+fn upcoming_deadlines_soonest_first() {
+    let keeper = deadlined();
+    let upcoming: Vec<_> = keeper
+        .upcoming_deadlines(&Filter::default())
+        .into_iter()
+        .map(|task| task.desc())
+        .collect();
+    // plan offsite is too far off to show up yet
+    assert_eq!(
+        upcoming,
+        [
+            "file expenses",
+            "submit grant",
+            "draft report",
+            "book venue",
+            "renew passport",
+        ]
+    );
+}
+
+#[test]
+fn countdown() {
+    let now = {
+        utc();
+        at("2024-02-01T13:30:00Z")
+    };
+    let due = |date_: &str, hour| Deadline {
+        date: date(date_),
+        hour,
+    };
+    assert_eq!(due("2024-02-01", Some(12)).countdown(now), "overdue");
+    assert_eq!(due("2024-02-01", None).countdown(now), "today");
+    assert_eq!(due("2024-02-02", Some(9)).countdown(now), "tomorrow");
+    assert_eq!(due("2024-02-05", None).countdown(now), "in 4 days");
+}
+
+#[test]
+fn shown_with_countdowns() {
+    let shown = shown(&deadlined(), ShowSet::Days(2), false);
+    assert!(shown.contains("[10] (draft report due 02 Feb 12:00)\n"));
+    assert!(shown.contains("[16] (book venue due 05 Feb)\n"));
+    assert!(shown.ends_with(
+        "\
+Deadlines
+[overdue] (file expenses due 01 Feb 12:00)
+[today] (submit grant due 01 Feb)
+[tomorrow] (draft report due 02 Feb 12:00)
+[in 4 days] (book venue due 05 Feb)
+[in 5 days] (renew passport due 06 Feb)
+"
+    ));
+}
@@ -0,0 +1,121 @@
+mod common;
+
+use common::{add, date, shown, utc};
+use keeper_todo::{
+    cli::ShowSet,
+    data::{ColorStyle, Keeper, KeeperDisplay},
+    filter::Filter,
+};
+
+/// review -> deploy -> announce, on two days, with ids assigned.
+fn pipeline() -> Keeper {
+    utc();
+    let today = date("2024-02-01");
+    let tomorrow = date("2024-02-02");
+    let mut keeper = Keeper::default();
+    add(&mut keeper, today, 9, "review", false);
+    add(&mut keeper, tomorrow, 10, "deploy", false);
+    add(&mut keeper, tomorrow, 11, "announce", false);
+    keeper.assign_ids();
+
+    let review = keeper.task(today, 9, 0).unwrap().id();
+    let deploy = keeper.task(tomorrow, 10, 0).unwrap().id();
+    keeper
+        .task_mut(tomorrow, 10, 0)
+        .unwrap()
+        .add_dependency(review);
+    keeper
+        .task_mut(tomorrow, 11, 0)
+        .unwrap()
+        .add_dependency(deploy);
+    keeper
+}
+
+#[test]
+fn ids_are_unique_and_stable() {
+    let mut keeper = pipeline();
+    let mut ids: Vec<_> = keeper.tasks().iter().map(|task| task.id()).collect();
+    assert!(ids.iter().all(|id| *id != 0));
+    ids.sort();
+    ids.dedup();
+    assert_eq!(ids.len(), 3);
+
+    // Assigning again doesn't change anything, new tasks get fresh ids
+    keeper.assign_ids();
+    add(&mut keeper, date("2024-02-01"), 9, "new", false);
+    keeper.assign_ids();
+    let new = keeper.task(date("2024-02-01"), 9, 1).unwrap().id();
+    assert!(!ids.contains(&new));
+    let again: Vec<_> = keeper.tasks().iter().map(|task| task.id()).collect();
+    assert!(ids.iter().all(|id| again.contains(id)));
+    assert_eq!(again.len(), 4);
+}
+
+#[test]
+fn blocked_until_dependencies_are_done() {
+    let mut keeper = pipeline();
+    let (today, tomorrow) = (date("2024-02-01"), date("2024-02-02"));
+    let deploy = keeper.task(tomorrow, 10, 0).unwrap();
+    let announce = keeper.task(tomorrow, 11, 0).unwrap();
+    assert!(keeper.blocked(deploy));
+    assert_eq!(keeper.blockers(deploy)[0].desc(), "review");
+    assert!(keeper.blocked(announce));
+    assert!(!keeper.blocked(keeper.task(today, 9, 0).unwrap()));
+
+    // The same through an index, like show and render use
+    let by_id = keeper.index();
+    assert_eq!(by_id.find(deploy.id()).unwrap().desc(), "deploy");
+    assert_eq!(by_id.blockers(deploy)[0].desc(), "review");
+    assert!(by_id.blocked(announce));
+
+    let now = keeper.clock().now();
+    keeper.task_mut(today, 9, 0).unwrap().mark_complete(now);
+    let deploy = keeper.task(tomorrow, 10, 0).unwrap();
+    let announce = keeper.task(tomorrow, 11, 0).unwrap();
+    assert!(!keeper.blocked(deploy));
+    assert!(keeper.blocked(announce));
+}
+
+#[test]
+fn cycles() {
+    let keeper = pipeline();
+    let id = |hour| {
+        let day = if hour == 9 {
+            "2024-02-01"
+        } else {
+            "2024-02-02"
+        };
+        keeper.task(date(day), hour, 0).unwrap().id()
+    };
+    let (review, deploy, announce) = (id(9), id(10), id(11));
+    assert!(keeper.depends_on(deploy, review));
+    assert!(keeper.depends_on(announce, review));
+    assert!(!keeper.depends_on(review, announce));
+    // So making review depend on announce would close a loop
+    assert!(keeper.depends_on(announce, review));
+}
+
+#[test]
+fn missing_dependencies_dont_block() {
+    let mut keeper = pipeline();
+    let tomorrow = date("2024-02-02");
+    keeper
+        .task_mut(tomorrow, 10, 0)
+        .unwrap()
+        .add_dependency(999);
+    keeper.days.remove(&date("2024-02-01"));
+    assert!(!keeper.blocked(keeper.task(tomorrow, 10, 0).unwrap()));
+}
+
+#[test]
+fn shown_as_waiting() {
+    let keeper = pipeline();
+    let tomorrow = ShowSet::Date(date("2024-02-02"));
+    let shown = shown(&keeper, tomorrow, true);
+    assert!(shown.contains("    deploy:\n        waiting on review\n"));
+    assert!(shown.contains("    announce:\n        waiting on deploy\n"));
+    // Blocked tasks are purple
+    let colored =
+        KeeperDisplay::new(&keeper, tomorrow, Filter::default(), ColorStyle::Color).to_string();
+    assert!(colored.contains("\x1b[0;35mdeploy"));
+}
@@ -0,0 +1,145 @@
+mod common;
+
+use common::{date, push, shown, workday};
+use keeper_todo::{
+    cli::ShowSet,
+    data::{Keeper, Task},
+    details::{ChecklistItem, Details},
+};
+
+fn item(done: bool, text: &str) -> ChecklistItem {
+    ChecklistItem {
+        done,
+        text: text.to_string(),
+    }
+}
+
+/// Tasks with notes, links and checklists.
+fn annotated() -> Keeper {
+    let mut keeper = workday();
+    let today = date("2024-02-01");
+    let mut trip = Task::new("plan trip".to_string());
+    trip.set_details(Details {
+        note: "Aim for the second week of March.\nAsk Sam about the car.".to_string(),
+        links: vec!["https://example.com/flights".to_string()],
+        checklist: vec![
+            item(true, "pick dates"),
+            item(true, "book flights"),
+            item(false, "book hotel"),
+            item(false, "rent car"),
+            item(false, "pack"),
+        ],
+    });
+    push(&mut keeper, today, 15, trip);
+
+    let mut reading = Task::new("read paper".to_string());
+    reading.set_details(Details {
+        links: vec!["https://example.com/paper.pdf".to_string()],
+        ..Details::default()
+    });
+    push(&mut keeper, today, 16, reading);
+
+    let mut chores = Task::new("chores".to_string());
+    chores.set_details(Details {
+        checklist: vec![item(true, "dishes"), item(true, "laundry")],
+        ..Details::default()
+    });
+    push(&mut keeper, today, 16, chores);
+    keeper
+}
+
+#[test]
+fn parse_sorts_lines() {
+    let text = "\
+<!-- keeper: notes for 'trip'.
+Lines starting with # used to be ignored. -->
+
+Remember to water the plants.
+- [ ] book flights
+https://example.com/itinerary
+- [x] renew passport
+  also call grandma
+
+- [X] pack
+";
+    let details = Details::parse(text);
+    assert_eq!(
+        details.note,
+        "Remember to water the plants.\n  also call grandma"
+    );
+    assert_eq!(details.links, ["https://example.com/itinerary"]);
+    assert_eq!(
+        details.checklist,
+        [
+            item(false, "book flights"),
+            item(true, "renew passport"),
+            item(true, "pack"),
+        ]
+    );
+    assert_eq!(details.progress(), Some((2, 3)));
+}
+
+#[test]
+fn round_trip() {
+    let details = Details {
+        note: "first line\n\nthird line".to_string(),
+        links: vec![
+            "http://a.example".to_string(),
+            "https://b.example".to_string(),
+        ],
+        checklist: vec![item(true, "done"), item(false, "not done")],
+    };
+    assert_eq!(Details::parse(&details.to_text()), details);
+}
+
+#[test]
+fn empty() {
+    let details = Details::parse("<!-- keeper: only help -->\n\n\n");
+    assert!(details.is_empty());
+    assert!(!details.has_attachments());
+    assert_eq!(details.progress(), None);
+    assert_eq!(details.to_text(), "");
+}
+
+#[test]
+fn keeps_headings() {
+    let text = "\
+<!-- keeper: notes for 'trip'.
+- [ ] not a checklist item, it's help
+-->
+# Plan
+Fly out on Friday.
+
+## Links
+https://example.com/itinerary
+";
+    let details = Details::parse(text);
+    assert_eq!(details.note, "# Plan\nFly out on Friday.\n\n## Links");
+    assert!(details.checklist.is_empty());
+    assert_eq!(Details::parse(&details.to_text()), details);
+}
+
+#[test]
+fn shown_with_markers() {
+    let keeper = annotated();
+    let today = ShowSet::Date(date("2024-02-01"));
+    let brief = shown(&keeper, today, false);
+    assert!(brief.contains("[15] (plan trip 📎 [2/5])\n"));
+    assert!(brief.contains("[16] (read paper 📎) (chores [2/2])\n"));
+    assert!(!brief.contains("Aim for"));
+
+    let verbose = shown(&keeper, today, true);
+    assert!(verbose.contains(
+        "\
+[15] (plan trip 📎 [2/5])
+    plan trip:
+        Aim for the second week of March.
+        Ask Sam about the car.
+        https://example.com/flights
+        [x] pick dates
+        [x] book flights
+        [ ] book hotel
+"
+    ));
+    assert!(verbose.contains("    chores:\n        [x] dishes\n        [x] laundry\n"));
+}
@@ -0,0 +1,82 @@
+mod common;
+
+use common::{assert_text_snapshot, busy, date, workday};
+use keeper_todo::{
+    cli::ShowSet,
+    data::{ColorStyle, KeeperDisplay},
+    filter::Filter,
+};
+
+#[test]
+fn display_day_color() {
+    let keeper = workday();
+    let shown = KeeperDisplay::new(
+        &keeper,
+        ShowSet::Date(date("2024-02-01")),
+        Filter::default(),
+        ColorStyle::Color,
+    );
+    assert_text_snapshot("day_color", &shown.to_string());
+}
+
+#[test]
+fn display_day_no_color() {
+    let keeper = workday();
+    let shown = KeeperDisplay::new(
+        &keeper,
+        ShowSet::Date(date("2024-02-01")),
+        Filter::default(),
+        ColorStyle::NoColor,
+    );
+    assert_text_snapshot("day_no_color", &shown.to_string());
+}
+
+#[test]
+fn display_days_no_color() {
+    let keeper = workday();
+    let shown = KeeperDisplay::new(
+        &keeper,
+        ShowSet::Days(3),
+        Filter::default(),
+        ColorStyle::NoColor,
+    );
+    assert_text_snapshot("days_no_color", &shown.to_string());
+}
+
+#[test]
+fn display_days_color() {
+    let keeper = workday();
+    let shown = KeeperDisplay::new(
+        &keeper,
+        ShowSet::Days(3),
+        Filter::default(),
+        ColorStyle::Color,
+    );
+    assert_text_snapshot("days_color", &shown.to_string());
+}
+
+#[test]
+fn display_everything() {
+    let keeper = busy();
+    let shown = KeeperDisplay::new(
+        &keeper,
+        ShowSet::Days(2),
+        Filter::default(),
+        ColorStyle::NoColor,
+    )
+    .verbose(true);
+    assert_text_snapshot("everything", &shown.to_string());
+}
+
+#[test]
+fn display_everything_color() {
+    let keeper = busy();
+    let shown = KeeperDisplay::new(
+        &keeper,
+        ShowSet::Days(2),
+        Filter::default(),
+        ColorStyle::Color,
+    )
+    .verbose(true);
+    assert_text_snapshot("everything_color", &shown.to_string());
+}
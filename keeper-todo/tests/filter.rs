@@ -0,0 +1,99 @@
+mod common;
+
+use common::{date, shown, tagged};
+use keeper_todo::{
+    cli::ShowSet,
+    data::{ColorStyle, Priority, Task, TaskList},
+    filter::{Expr, Filter},
+};
+
+fn task(tags: &[&str]) -> Task {
+    let mut task = Task::new("task".to_string());
+    for tag in tags {
+        task.add_tag(tag);
+    }
+    task
+}
+
+fn matches(expr: &str, tags: &[&str]) -> bool {
+    Expr::parse(expr).unwrap().matches(&task(tags))
+}
+
+#[test]
+fn single_tag() {
+    assert!(matches("+work", &["+work", "@office"]));
+    assert!(matches("@office", &["+work", "@office"]));
+    assert!(!matches("+home", &["+work"]));
+    // Tags are case insensitive
+    assert!(matches("+Work", &["+work"]));
+}
+
+#[test]
+fn operators() {
+    assert!(matches("+work and @office", &["+work", "@office"]));
+    assert!(!matches("+work & @office", &["+work"]));
+    assert!(matches("+work or +home", &["+home"]));
+    assert!(matches("+work | +home", &["+work"]));
+    assert!(matches("not +work", &["+home"]));
+    assert!(!matches("!+work", &["+work"]));
+    // Tags next to each other are and-ed
+    assert!(matches("+work @office", &["+work", "@office"]));
+    assert!(!matches("+work @office", &["+work"]));
+}
+
+#[test]
+fn precedence() {
+    // and binds tighter than or
+    assert!(matches("+a or +b and +c", &["+a"]));
+    assert!(!matches("(+a or +b) and +c", &["+a"]));
+    // not binds tighter than and
+    assert!(matches("not +a and +b", &["+b"]));
+    assert!(!matches("not (+a and +b) or +c", &["+a", "+b"]));
+}
+
+#[test]
+fn invalid() {
+    assert!(Expr::parse("").is_err());
+    assert!(Expr::parse("work").is_err());
+    assert!(Expr::parse("(+work").is_err());
+    assert!(Expr::parse("+work)").is_err());
+    assert!(Expr::parse("+work and").is_err());
+}
+
+#[test]
+fn filter_combines_priority_and_tags() {
+    let filter = Filter {
+        priority: Some(Priority::High),
+        tags: Some(Expr::parse("+work").unwrap()),
+    };
+    let mut urgent = Task::with_priority("task".to_string(), Priority::Urgent);
+    urgent.add_tag("+work");
+    let mut low = Task::with_priority("task".to_string(), Priority::Low);
+    low.add_tag("+work");
+    assert!(filter.matches(&urgent));
+    assert!(!filter.matches(&low));
+    assert!(!filter.matches(&task(&["+work"])));
+    assert!(Filter::default().matches(&task(&[])));
+}
+
+#[test]
+fn tags_in_show_and_list() {
+    let keeper = tagged();
+    let shown = shown(&keeper, ShowSet::Date(date("2024-02-01")), false);
+    assert!(shown.contains("[10] (call the bank +home @phone)\n"));
+    assert!(shown.contains("[15] (fix login bug +work) (ping design +work @phone)\n"));
+
+    let filter = Filter {
+        tags: Some(Expr::parse("(+work or +home) and not @phone").unwrap()),
+        ..Filter::default()
+    };
+    let listed = TaskList::new(&keeper, filter, ColorStyle::NoColor);
+    assert_eq!(
+        listed.to_string(),
+        "\
+01-02-2024 15.0 (fix login bug +work)
+01-02-2024 18.0 (laundry +home)
+02-02-2024 9.0 (sprint planning +work @office)
+"
+    );
+}
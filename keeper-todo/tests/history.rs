@@ -0,0 +1,93 @@
+mod common;
+
+use chrono::{DateTime, Local};
+use common::{assert_text_snapshot, at, date, done_at, utc, workday};
+use keeper_todo::{
+    cli::ShowSet,
+    data::{ColorStyle, History, Keeper, Task},
+    filter::Filter,
+};
+use keeper_util::clock::Clock;
+
+#[test]
+fn history_by_completion_day() {
+    let mut keeper = workday();
+    // Planned for yesterday, but only got done this morning
+    let mut late = Task::new("file report".to_string());
+    late.mark_complete(at("2024-02-01T10:10:00Z"));
+    common::push(&mut keeper, date("2024-01-31"), 16, late);
+    let history = History::new(
+        &keeper,
+        ShowSet::Days(3),
+        Filter::default(),
+        ColorStyle::NoColor,
+    );
+    assert_text_snapshot("history", &history.to_string());
+}
+
+#[test]
+fn completion_times() {
+    utc();
+    let today = date("2024-02-01");
+    let mut release = Task::new("ship release".to_string());
+    release.add_subtask(Task::new("write changelog".to_string()));
+    release.add_subtask(Task::new("bump version".to_string()));
+
+    release.mark_path(&[0], done_at(today, 9));
+    assert_eq!(release.completed_at(), None);
+    release.mark_path(&[1], done_at(today, 10));
+    // The parent is done once its last subtask is
+    assert_eq!(release.completed_at(), Some(done_at(today, 10)));
+    assert_eq!(
+        release.subtasks()[0].completed_at(),
+        Some(done_at(today, 9))
+    );
+    assert_eq!(release.modified(), Some(done_at(today, 10)));
+
+    release.add_subtask(Task::new("announce".to_string()));
+    assert_eq!(release.completed_at(), None);
+    // Already finished subtasks keep their time when the parent is marked
+    release.mark_complete(done_at(today, 11));
+    assert_eq!(
+        release.subtasks()[0].completed_at(),
+        Some(done_at(today, 9))
+    );
+    assert_eq!(
+        release.subtasks()[2].completed_at(),
+        Some(done_at(today, 11))
+    );
+}
+
+#[test]
+fn backfill_old_data() {
+    utc();
+    let mut keeper: Keeper = ron::from_str(
+        r#"(
+            days: {
+                "2024-01-31": (timeslots: {
+                    14: [(completed: true, desc: "old and done")],
+                    15: [(completed: false, desc: "old")],
+                }),
+                "2024-02-05": (timeslots: {
+                    9: [(completed: true, desc: "done early")],
+                }),
+            },
+        )"#,
+    )
+    .unwrap();
+    let now: DateTime<Local> = at("2024-02-01T13:30:00Z");
+    keeper.set_clock(Clock::Fixed(now));
+    keeper.backfill_timestamps();
+
+    let yesterday = date("2024-01-31");
+    let done = keeper.task(yesterday, 14, 0).unwrap();
+    assert_eq!(done.created(), Some(at("2024-01-31T00:00:00Z")));
+    assert_eq!(done.completed_at(), Some(at("2024-01-31T14:59:59Z")));
+    let open = keeper.task(yesterday, 15, 0).unwrap();
+    assert_eq!(open.modified(), Some(at("2024-01-31T00:00:00Z")));
+    assert_eq!(open.completed_at(), None);
+    // Nothing can have happened after now
+    let early = keeper.task(date("2024-02-05"), 9, 0).unwrap();
+    assert_eq!(early.created(), Some(now));
+    assert_eq!(early.completed_at(), Some(now));
+}
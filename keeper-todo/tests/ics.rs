@@ -0,0 +1,125 @@
+mod common;
+
+use common::{assert_text_snapshot, at, date, mixed, utc};
+use keeper_todo::{data::Keeper, ics};
+use keeper_util::clock::Clock;
+
+fn empty() -> Keeper {
+    utc();
+    let mut keeper = Keeper::default();
+    keeper.set_clock(Clock::Fixed(at("2024-02-01T13:30:00Z")));
+    keeper
+}
+
+#[test]
+fn export_ics() {
+    let keeper = mixed();
+    assert_text_snapshot("ics", &ics::export(&keeper, None, None, false));
+}
+
+#[test]
+fn export_range_as_events() {
+    let keeper = mixed();
+    let day = Some(date("2024-02-02"));
+    let ics = ics::export(&keeper, day, day, true);
+    assert_eq!(ics.matches("BEGIN:VEVENT").count(), 1);
+    assert!(ics.contains("SUMMARY:dentist\r\nDTSTART:20240202T100000\r\nDURATION:PT1H\r\n"));
+    assert!(!ics.contains("STATUS"));
+}
+
+#[test]
+fn ics_round_trip() {
+    let keeper = mixed();
+    let exported = ics::export(&keeper, None, None, false);
+    let mut imported = empty();
+    let tasks = ics::import(&exported, imported.clock().now()).unwrap();
+    assert_eq!(imported.import(tasks), (16, 0));
+    assert_eq!(ics::export(&imported, None, None, false), exported);
+}
+
+#[test]
+fn ics_import_deduplicates() {
+    let mut keeper = mixed();
+    let exported = ics::export(&keeper, None, None, false);
+    let tasks = ics::import(&exported, keeper.clock().now()).unwrap();
+    assert_eq!(keeper.import(tasks), (0, 16));
+    assert_eq!(ics::export(&keeper, None, None, false), exported);
+
+    // Moved to another hour and done in the calendar
+    let moved = exported
+        .replace("DTSTART:20240201T180000", "DTSTART:20240202T080000")
+        .replace(
+            "SUMMARY:chores\r\nDTSTART:20240202T080000\r\nDUE;VALUE=DATE:20240203\r\nSTATUS:NEEDS-ACTION",
+            "SUMMARY:chores\r\nDTSTART:20240202T080000\r\nDUE;VALUE=DATE:20240203\r\nSTATUS:COMPLETED",
+        );
+    let tasks = ics::import(&moved, keeper.clock().now()).unwrap();
+    assert_eq!(keeper.import(tasks), (0, 16));
+    assert!(!keeper.days[&date("2024-02-01")].timeslots.contains_key(&18));
+    let chores = keeper.task(date("2024-02-02"), 8, 0).unwrap();
+    assert_eq!(chores.desc(), "chores");
+    assert!(chores.completed());
+}
+
+#[test]
+fn ics_import_from_elsewhere() {
+    let ics = "\
+BEGIN:VCALENDAR\r
+VERSION:2.0\r
+PRODID:-//Example//Calendar//EN\r
+BEGIN:VEVENT\r
+UID:abc123@example.com\r
+DTSTAMP:20240130T120000Z\r
+DTSTART;TZID=Europe/London:20240205T140000\r
+DTEND;TZID=Europe/London:20240205T153000\r
+SUMMARY:Team offsite\\, day one\r
+DESCRIPTION:Bring the slides.\\nhttps://example.com/agenda\r
+CATEGORIES:work,@office\r
+RRULE:FREQ=WEEKLY;COUNT=3\r
+BEGIN:VALARM\r
+ACTION:DISPLAY\r
+DESCRIPTION:Reminder\r
+TRIGGER:-PT15M\r
+END:VALARM\r
+END:VEVENT\r
+BEGIN:VTODO\r
+UID:todo-1@example.com\r
+DTSTAMP:20240130T120000Z\r
+SUMMARY:Send invoice with a summary long enough that the calendar had to\r
+  fold it\r
+DUE:20240206T170000Z\r
+PRIORITY:2\r
+END:VTODO\r
+BEGIN:VEVENT\r
+UID:holiday@example.com\r
+DTSTAMP:20240130T120000Z\r
+DTSTART;VALUE=DATE:20240207\r
+SUMMARY:Holiday\r
+STATUS:CONFIRMED\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+    let mut keeper = empty();
+    let tasks = ics::import(ics, keeper.clock().now()).unwrap();
+    assert_eq!(keeper.import(tasks), (3, 0));
+
+    let offsite = keeper.task(date("2024-02-05"), 14, 0).unwrap();
+    assert_eq!(offsite.desc(), "Team offsite, day one");
+    assert_eq!(offsite.tags(), ["+work", "@office"]);
+    assert_eq!(offsite.details().note, "Bring the slides.");
+    assert_eq!(offsite.details().links, ["https://example.com/agenda"]);
+
+    let invoice = &keeper.inbox[0];
+    assert_eq!(
+        invoice.desc(),
+        "Send invoice with a summary long enough that the calendar had to fold it"
+    );
+    let deadline = invoice.deadline().unwrap();
+    assert_eq!(
+        (deadline.date, deadline.hour),
+        (date("2024-02-06"), Some(16))
+    );
+
+    // All-day events go at the start of the day
+    let holiday = keeper.task(date("2024-02-07"), 9, 0).unwrap();
+    assert_eq!(holiday.desc(), "Holiday");
+}
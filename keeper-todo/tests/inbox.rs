@@ -0,0 +1,53 @@
+mod common;
+
+use common::inboxed;
+use keeper_todo::{
+    data::{ColorStyle, InboxList, Priority},
+    filter::Filter,
+};
+
+#[test]
+fn inbox_is_ordered() {
+    let keeper = inboxed();
+    let inbox: Vec<_> = keeper
+        .inbox
+        .iter()
+        .map(|task| (task.completed(), task.priority()))
+        .collect();
+    assert_eq!(
+        inbox,
+        [
+            (false, Priority::High),
+            (false, Priority::Normal),
+            (false, Priority::Low),
+            (true, Priority::Normal),
+        ]
+    );
+}
+
+#[test]
+fn inbox_tasks_get_ids() {
+    let mut keeper = inboxed();
+    keeper.assign_ids();
+    assert!(keeper.inbox.iter().all(|task| task.id() != 0));
+    // So they can be depended on like any other task
+    let id = keeper.inbox[0].id();
+    assert_eq!(keeper.find(id).unwrap().desc(), "renew passport");
+    let scheduled = keeper.tasks().len() - keeper.inbox.len();
+    assert_eq!(scheduled, common::workday().tasks().len());
+}
+
+#[test]
+fn listed_with_indices() {
+    let keeper = inboxed();
+    let listed = InboxList::new(&keeper, Filter::default(), ColorStyle::NoColor);
+    assert_eq!(
+        listed.to_string(),
+        "\
+0 (! renew passport +home)
+1 (write a blog post about keeper +blog)
+2 (~ learn the cello)
+3 (fix bike)
+"
+    );
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use common::{assert_text_snapshot, blocked, date, fortnight, mixed, workday};
+use keeper_todo::{
+    cli::ShowSet,
+    data::TaskStatus,
+    filter::Filter,
+    output::{self, Output, OutputFormat, StatsRecord},
+    search::{Pattern, Search},
+    stats::{Period, Stats},
+};
+
+#[test]
+fn show_json() {
+    let keeper = mixed();
+    let records = output::show(&keeper, ShowSet::Days(2), &Filter::default());
+    assert_text_snapshot("output_show", &records.format(OutputFormat::Json).unwrap());
+}
+
+#[test]
+fn status_like_colors() {
+    let keeper = workday();
+    let records = output::show(
+        &keeper,
+        ShowSet::Date(date("2024-02-01")),
+        &Filter::default(),
+    );
+    let status = |desc: &str| {
+        let record = records.iter().find(|record| record.desc == desc).unwrap();
+        record.status
+    };
+    assert_eq!(status("standup"), TaskStatus::Done);
+    // It's 13:30, so the morning is over
+    assert_eq!(status("email triage"), TaskStatus::Overdue);
+    assert_eq!(status("write docs"), TaskStatus::Pending);
+
+    let keeper = blocked();
+    let records = output::show(
+        &keeper,
+        ShowSet::Date(date("2024-02-01")),
+        &Filter::default(),
+    );
+    let deploy = records
+        .iter()
+        .find(|record| record.desc == "deploy")
+        .unwrap();
+    assert!(deploy.blocked);
+    assert_eq!(deploy.status, TaskStatus::Pending);
+    assert_eq!(deploy.id, "16.0");
+}
+
+#[test]
+fn subtask_ids() {
+    let keeper = mixed();
+    let records = output::show(
+        &keeper,
+        ShowSet::Date(date("2024-02-01")),
+        &Filter::default(),
+    );
+    let ids: Vec<_> = records
+        .iter()
+        .filter(|record| record.hour == Some(15))
+        .map(|record| record.id.as_str())
+        .collect();
+    assert_eq!(
+        ids,
+        ["15.0", "15.0.0", "15.0.1", "15.0.1.0", "15.0.1.1", "15.0.2"]
+    );
+}
+
+#[test]
+fn search_csv() {
+    let keeper = mixed();
+    let hits = Search::new(Pattern::Substring("e".to_string())).hits(&keeper);
+    let records = output::search(&keeper, &hits);
+    assert_text_snapshot("output_search", &records.format(OutputFormat::Csv).unwrap());
+}
+
+#[test]
+fn history_ron() {
+    let keeper = workday();
+    let records = output::history(&keeper, ShowSet::Days(3), &Filter::default());
+    let descs: Vec<_> = records.iter().map(|record| record.desc.as_str()).collect();
+    assert_eq!(descs, ["standup", "review PR", "lunch"]);
+    assert_text_snapshot(
+        "output_history",
+        &records.format(OutputFormat::Ron).unwrap(),
+    );
+}
+
+#[test]
+fn stats_json() {
+    let keeper = fortnight();
+    let stats = Stats::new(&keeper, 14);
+    let record = StatsRecord::new(&stats, Period::Week);
+    let json: serde_json::Value =
+        serde_json::from_str(&record.format(OutputFormat::Json).unwrap()).unwrap();
+    assert_eq!(json["period"], "week");
+    assert_eq!(json["periods"][1]["start"], "2024-01-22");
+    assert_eq!(json["periods"][1]["done"], 17);
+    assert_eq!(json["days"].as_array().unwrap().len(), 14);
+
+    let csv = record.format(OutputFormat::Csv).unwrap();
+    assert_eq!(
+        csv,
+        "start,done,total,rate\r\n\
+         2024-01-15,2,2,100\r\n\
+         2024-01-22,17,19,89\r\n\
+         2024-01-29,8,13,62\r\n"
+    );
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use common::{date, push, shown, workday};
+use keeper_todo::{
+    cli::ShowSet,
+    data::{ColorStyle, Keeper, KeeperDisplay, Priority, Task},
+    filter::Filter,
+};
+
+/// A day with tasks of every priority, not yet in order.
+fn prioritized() -> Keeper {
+    let mut keeper = workday();
+    let today = date("2024-02-01");
+    let tasks = [
+        (15, "water plants", Priority::Low),
+        (15, "file taxes", Priority::Urgent),
+        (15, "call mom", Priority::Normal),
+        (15, "renew passport", Priority::High),
+        (16, "read", Priority::Low),
+    ];
+    for (hour, desc, priority) in tasks {
+        push(
+            &mut keeper,
+            today,
+            hour,
+            Task::with_priority(desc.to_string(), priority),
+        );
+    }
+    keeper.order();
+    keeper
+}
+
+#[test]
+fn ordered_by_priority() {
+    let keeper = prioritized();
+    let today = date("2024-02-01");
+    let descs: Vec<_> = keeper.days[&today].timeslots[&15]
+        .iter()
+        .map(|task| task.desc())
+        .collect();
+    assert_eq!(
+        descs,
+        ["file taxes", "renew passport", "call mom", "water plants"]
+    );
+    let shown = shown(&keeper, ShowSet::Date(today), false);
+    assert!(shown.contains("[15] (!! file taxes) (! renew passport) (call mom) (~ water plants)\n"));
+    assert!(shown.contains("[16] (~ read)\n"));
+}
+
+#[test]
+fn show_filter() {
+    let keeper = prioritized();
+    let filter = Filter {
+        priority: Some(Priority::High),
+        ..Filter::default()
+    };
+    let shown = KeeperDisplay::new(
+        &keeper,
+        ShowSet::Date(date("2024-02-01")),
+        filter,
+        ColorStyle::NoColor,
+    );
+    assert_eq!(
+        shown.to_string(),
+        "01 Feb 2024\n[15] (!! file taxes) (! renew passport)\n"
+    );
+}
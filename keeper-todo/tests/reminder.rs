@@ -0,0 +1,44 @@
+mod common;
+
+use common::{add, at, date, workday};
+use keeper_todo::{
+    config::ReminderConfig,
+    reminder::{ReminderState, TaskKey},
+};
+
+#[test]
+fn snooze_follows_the_task() {
+    let mut keeper = workday();
+    keeper.assign_ids();
+    let config = ReminderConfig::default();
+    let now = at("2024-02-01T13:30:00Z");
+    // Nothing written, so this starts out empty
+    let mut state = ReminderState::load(&std::env::temp_dir().join("keeper-no-such-dir")).unwrap();
+    assert_eq!(
+        state.next(&keeper, &config, now),
+        Some(at("2024-02-01T13:50:00Z"))
+    );
+
+    let docs = keeper.task(date("2024-02-01"), 14, 0).unwrap();
+    state.snooze(TaskKey::of(docs), at("2024-02-01T14:30:00Z"));
+    assert_eq!(
+        state.next(&keeper, &config, now),
+        Some(at("2024-02-01T14:30:00Z"))
+    );
+
+    // Renaming it keeps the snooze, and another task with its old name isn't snoozed
+    keeper
+        .task_mut(date("2024-02-01"), 14, 0)
+        .unwrap()
+        .set_desc("write more docs".to_string());
+    assert_eq!(
+        state.next(&keeper, &config, now),
+        Some(at("2024-02-01T14:30:00Z"))
+    );
+    add(&mut keeper, date("2024-02-01"), 14, "write docs", false);
+    keeper.assign_ids();
+    assert_eq!(
+        state.next(&keeper, &config, now),
+        Some(at("2024-02-01T13:50:00Z"))
+    );
+}
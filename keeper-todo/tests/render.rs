@@ -0,0 +1,134 @@
+mod common;
+
+use common::{assert_image_snapshot, busy, date, font_path, fonts, packed, wordy, workday};
+use keeper_todo::{
+    cli::ShowSet,
+    config::FontConfig,
+    data::Keeper,
+    filter::Filter,
+    font::Fonts,
+    render::{KeeperRenderer, NORD_BG},
+};
+
+fn render(name: &str, keeper: &Keeper, set: ShowSet, size: (u32, u32)) {
+    render_filtered(name, keeper, set, Filter::default(), size);
+}
+
+fn render_filtered(name: &str, keeper: &Keeper, set: ShowSet, filter: Filter, size: (u32, u32)) {
+    let fonts = fonts();
+    let mut renderer = KeeperRenderer::new(keeper, set, filter, NORD_BG, &fonts, size);
+    renderer.render();
+    assert_image_snapshot(name, renderer.image());
+}
+
+#[test]
+fn render_today() {
+    let keeper = workday();
+    render(
+        "today",
+        &keeper,
+        ShowSet::Date(date("2024-02-01")),
+        (1470, 956),
+    );
+}
+
+#[test]
+fn render_days() {
+    let keeper = workday();
+    render("days", &keeper, ShowSet::Days(3), (1470, 956));
+}
+
+#[test]
+fn render_empty_day() {
+    let keeper = workday();
+    render(
+        "empty",
+        &keeper,
+        ShowSet::Date(date("2024-01-01")),
+        (1470, 956),
+    );
+}
+
+#[test]
+fn render_wrapped() {
+    let keeper = wordy();
+    render(
+        "wrapped",
+        &keeper,
+        ShowSet::Date(date("2024-02-01")),
+        (1470, 956),
+    );
+}
+
+#[test]
+fn render_overflow() {
+    let keeper = packed();
+    render(
+        "overflow",
+        &keeper,
+        ShowSet::Date(date("2024-02-01")),
+        (1470, 956),
+    );
+}
+
+#[test]
+fn render_portrait() {
+    let keeper = wordy();
+    render("portrait", &keeper, ShowSet::Days(2), (1080, 1920));
+}
+
+#[test]
+fn render_everything() {
+    let keeper = busy();
+    let fonts = fonts();
+    let set = ShowSet::Date(date("2024-02-01"));
+    let mut renderer = KeeperRenderer::new(
+        &keeper,
+        set,
+        Filter::default(),
+        NORD_BG,
+        &fonts,
+        (1470, 956),
+    );
+    renderer.set_show_inbox(true);
+    renderer.render();
+    assert_image_snapshot("everything", renderer.image());
+}
+
+#[test]
+fn fake_bold() {
+    let keeper = workday();
+    let set = ShowSet::Date(date("2024-01-01"));
+    let ink = |fonts: &Fonts| {
+        let mut renderer =
+            KeeperRenderer::new(&keeper, set, Filter::default(), NORD_BG, fonts, (1470, 956));
+        renderer.render();
+        renderer
+            .image()
+            .pixels()
+            .filter(|pixel| **pixel != NORD_BG)
+            .count()
+    };
+    // The same font as its own bold draws the header like any other text
+    let font = font_path().to_string_lossy().to_string();
+    let plain = Fonts::load(&FontConfig {
+        regular: Some(font.clone()),
+        bold: Some(font),
+        ..FontConfig::default()
+    })
+    .unwrap();
+    assert!(ink(&fonts()) > ink(&plain));
+}
+
+#[test]
+fn render_tiny() {
+    let keeper = workday();
+    let fonts = fonts();
+    for size in [(0, 0), (10, 956), (1470, 10)] {
+        let set = ShowSet::Date(date("2024-02-01"));
+        let mut renderer =
+            KeeperRenderer::new(&keeper, set, Filter::default(), NORD_BG, &fonts, size);
+        // Nothing to draw in, but nothing to panic about either
+        renderer.render();
+    }
+}
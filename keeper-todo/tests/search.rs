@@ -0,0 +1,87 @@
+mod common;
+
+use common::{assert_text_snapshot, date, inboxed, nested, tagged};
+use keeper_todo::{
+    data::{ColorStyle, SearchResults},
+    filter::{Expr, Filter},
+    search::{Pattern, Search, Status},
+};
+use regex::RegexBuilder;
+
+fn descs(keeper: &keeper_todo::data::Keeper, search: &Search) -> Vec<String> {
+    search
+        .hits(keeper)
+        .iter()
+        .map(|hit| hit.task.desc().to_string())
+        .collect()
+}
+
+#[test]
+fn substring_search() {
+    let keeper = tagged();
+    let search = Search::new(Pattern::Substring("PING".to_string()));
+    assert_eq!(descs(&keeper, &search), ["ping design"]);
+    // Tags are searched too
+    let search = Search::new(Pattern::Substring("@phone".to_string()));
+    assert_eq!(descs(&keeper, &search), ["call the bank", "ping design"]);
+}
+
+#[test]
+fn regex_search() {
+    let keeper = tagged();
+    let regex = RegexBuilder::new("^(call|ping) ")
+        .case_insensitive(true)
+        .build()
+        .unwrap();
+    let search = Search::new(Pattern::Regex(regex));
+    assert_eq!(descs(&keeper, &search), ["call the bank", "ping design"]);
+}
+
+#[test]
+fn fuzzy_search() {
+    let keeper = tagged();
+    let search = Search::new(Pattern::Fuzzy("dntst".to_string()));
+    assert_eq!(descs(&keeper, &search), ["dentist"]);
+    // Letters that start words count for more
+    let search = Search::new(Pattern::Fuzzy("pd".to_string()));
+    assert_eq!(descs(&keeper, &search)[0], "ping design");
+}
+
+#[test]
+fn search_filters() {
+    let keeper = tagged();
+    let mut search = Search::new(Pattern::Substring(String::new()));
+    search.status = Some(Status::Done);
+    search.to = Some(date("2024-02-01"));
+    assert_eq!(descs(&keeper, &search), ["standup", "review PR", "lunch"]);
+
+    let mut search = Search::new(Pattern::Substring(String::new()));
+    search.status = Some(Status::Overdue);
+    search.filter = Filter {
+        priority: None,
+        tags: Some(Expr::parse("+home").unwrap()),
+    };
+    assert_eq!(descs(&keeper, &search), ["call the bank"]);
+
+    let mut search = Search::new(Pattern::Substring(String::new()));
+    search.from = Some(date("2024-02-02"));
+    assert_eq!(descs(&keeper, &search), ["sprint planning", "dentist"]);
+}
+
+#[test]
+fn search_subtasks() {
+    let keeper = nested();
+    let search = Search::new(Pattern::Substring("announce".to_string()));
+    let hits = search.hits(&keeper);
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].slot, Some((date("2024-02-01"), 15)));
+    assert_eq!(hits[0].path, [0, 2, 1]);
+}
+
+#[test]
+fn search_results() {
+    let keeper = inboxed();
+    let search = Search::new(Pattern::Substring("re".to_string()));
+    let results = SearchResults::new(&keeper, search.hits(&keeper), ColorStyle::NoColor);
+    assert_text_snapshot("search", &results.to_string());
+}
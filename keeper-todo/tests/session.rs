@@ -0,0 +1,127 @@
+mod common;
+
+use common::{at, date, tagged, workday};
+use keeper_todo::{
+    cli::Command,
+    data::{Priority, Task},
+    session::{parse_line, LineHelper},
+};
+use keeper_util::{clock::Clock, recover};
+
+fn clock() -> Clock {
+    common::utc();
+    Clock::Fixed(at("2024-02-01T13:30:00Z"))
+}
+
+#[test]
+fn quoted_lines() {
+    let command = parse_line(r#"add today 15 "call mom +family" -p high"#, clock()).unwrap();
+    let Some(Command::Add {
+        date: day,
+        hour,
+        desc,
+        priority,
+        tags,
+        ..
+    }) = command
+    else {
+        panic!("not an add: {command:?}");
+    };
+    assert_eq!(day, date("2024-02-01"));
+    assert_eq!(hour, 15);
+    assert_eq!(desc, "call mom");
+    assert_eq!(priority, Priority::High);
+    assert_eq!(tags, ["+family"]);
+
+    // Pasted from a shell
+    let command = parse_line("keeper-todo mark today 9.1", clock()).unwrap();
+    assert!(matches!(command, Some(Command::Mark { hour: 9, .. })));
+}
+
+#[test]
+fn skips_blanks_and_comments() {
+    assert!(parse_line("", clock()).unwrap().is_none());
+    assert!(parse_line("   ", clock()).unwrap().is_none());
+    assert!(parse_line("# plan for monday", clock()).unwrap().is_none());
+}
+
+#[test]
+fn rejects_long_running() {
+    for line in [
+        "tui",
+        "daemon",
+        "status waybar --watch",
+        "repl",
+        "batch plan.txt",
+    ] {
+        assert!(parse_line(line, clock()).is_err(), "{line} was accepted");
+    }
+    assert!(parse_line("status waybar", clock()).unwrap().is_some());
+    assert!(parse_line("add today 15 'unclosed", clock()).is_err());
+}
+
+#[test]
+fn recovers_from_fatal() {
+    // These would exit the process outside of `recover`
+    assert!(recover(|| parse_line("add", clock())).is_none());
+    assert!(recover(|| parse_line("frobnicate", clock())).is_none());
+    let command = recover(|| parse_line("show today", clock()));
+    assert!(matches!(command, Some(Ok(Some(Command::Show { .. })))));
+}
+
+#[test]
+fn only_changes_are_saved() {
+    let changes = |line| parse_line(line, clock()).unwrap().unwrap().changes_data();
+    assert!(changes("add today 15 'call mom'"));
+    assert!(changes("mark today 9.1"));
+    assert!(!changes("show today"));
+    assert!(!changes("search mom"));
+    assert!(!changes("stats"));
+    assert!(!changes("render today"));
+}
+
+#[test]
+fn change_past_the_end() {
+    let mut keeper = workday();
+    keeper.defer_render(true);
+    // There are two tasks at 9, so 9.2 is one past the end
+    assert!(recover(|| keeper.change(date("2024-02-01"), 9, 2, 10)).is_none());
+    assert_eq!(keeper.days[&date("2024-02-01")].timeslots[&9].len(), 2);
+}
+
+#[test]
+fn deferred_render() {
+    let mut keeper = workday();
+    keeper.defer_render(true);
+    // Rendering would need fd and a wallpaper directory
+    keeper
+        .add(date("2024-02-01"), 15, Task::new("call mom".to_string()))
+        .unwrap();
+    assert_eq!(
+        keeper.task(date("2024-02-01"), 15, 0).unwrap().desc(),
+        "call mom"
+    );
+}
+
+#[test]
+fn completion() {
+    let keeper = tagged();
+    let helper = LineHelper::new(&keeper);
+    assert_eq!(helper.candidates("sh", 2), (0, vec!["show".to_string()]));
+    assert_eq!(
+        helper.candidates("e", 1),
+        (0, vec!["export".to_string(), "exit".to_string()])
+    );
+    assert_eq!(
+        helper.candidates("add to", 6),
+        (4, vec!["today".to_string(), "tomorrow".to_string()])
+    );
+    assert_eq!(
+        helper.candidates("add today 15 call +", 19),
+        (18, vec!["+home".to_string(), "+work".to_string()])
+    );
+    assert_eq!(
+        helper.candidates("list @ph", 8),
+        (5, vec!["@phone".to_string()])
+    );
+}
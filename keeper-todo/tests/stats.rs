@@ -0,0 +1,78 @@
+mod common;
+
+use common::{assert_image_snapshot, assert_text_snapshot, fonts, fortnight, workday};
+use keeper_todo::{
+    data::ColorStyle,
+    stats::{Period, Stats, StatsReport},
+};
+
+#[test]
+fn completion_by_period() {
+    let keeper = fortnight();
+    let stats = Stats::new(&keeper, 14);
+    // 19 Jan up to today
+    assert_eq!(stats.days.len(), 14);
+    let weeks = stats.by(Period::Week);
+    assert_eq!(
+        weeks,
+        [
+            ("15 Jan".to_string(), 2, 2),
+            ("22 Jan".to_string(), 17, 19),
+            ("29 Jan".to_string(), 8, 13),
+        ]
+    );
+    let months = stats.by(Period::Month);
+    assert_eq!(
+        months,
+        [
+            ("Jan 2024".to_string(), 24, 28),
+            ("Feb 2024".to_string(), 3, 6)
+        ]
+    );
+}
+
+#[test]
+fn overdue_and_streaks() {
+    let keeper = fortnight();
+    let stats = Stats::new(&keeper, 14);
+    // 1 + 1 + 2 misses before today, and email triage today
+    let overdue: usize = stats.days.iter().map(|day| day.overdue).sum();
+    assert_eq!(overdue, 5);
+    assert_eq!(stats.average_overdue(), 5.0 / 12.0);
+    // Today isn't over yet, so it doesn't break the streak from the 31st
+    assert_eq!(stats.streaks(), (1, 3));
+}
+
+#[test]
+fn busiest_hours_and_deferred() {
+    let keeper = fortnight();
+    let stats = Stats::new(&keeper, 14);
+    assert_eq!(stats.busiest_hours()[0], (9, 14));
+    let deferred: Vec<_> = stats.deferred.iter().map(|task| task.desc()).collect();
+    assert_eq!(deferred, ["write docs", "file taxes"]);
+}
+
+#[test]
+fn stats_report() {
+    let keeper = fortnight();
+    let stats = Stats::new(&keeper, 14);
+    let report = StatsReport::new(&stats, Period::Week, ColorStyle::NoColor);
+    assert_text_snapshot("stats", &report.to_string());
+}
+
+#[test]
+fn stats_chart() {
+    let keeper = fortnight();
+    let stats = Stats::new(&keeper, 14);
+    let fonts = fonts();
+    assert_image_snapshot("stats", &stats.chart(Period::Day, &fonts, (1200, 600)));
+}
+
+#[test]
+fn no_days() {
+    let keeper = workday();
+    let stats = Stats::new(&keeper, 0);
+    assert!(stats.by(Period::Week).is_empty());
+    assert_eq!(stats.streaks(), (0, 0));
+    assert_eq!(stats.average_overdue(), 0.0);
+}
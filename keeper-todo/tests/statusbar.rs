@@ -0,0 +1,91 @@
+mod common;
+
+use common::{add, at, blocked, date, deadlined, utc, workday};
+use keeper_todo::{
+    data::Keeper,
+    statusbar::{Bar, BarState},
+};
+use keeper_util::clock::Clock;
+
+#[test]
+fn next_and_overdue() {
+    let keeper = workday();
+    let state = BarState::new(&keeper);
+    assert_eq!(state.overdue, 1);
+    assert_eq!(state.next.unwrap().desc, "write docs");
+
+    let state = BarState::new(&keeper);
+    assert_eq!(state.format(Bar::Plain), "1 overdue · 14:00 write docs");
+    assert_eq!(
+        state.format(Bar::Polybar),
+        "%{F#BF616A}1 overdue%{F-} · 14:00 write docs"
+    );
+    assert_eq!(
+        state.format(Bar::I3blocks),
+        "<span color=\"#BF616A\">1 overdue</span> · 14:00 write docs"
+    );
+    assert_eq!(
+        state.format(Bar::Tmux),
+        "#[fg=red]1 overdue#[fg=default] · 14:00 write docs"
+    );
+}
+
+#[test]
+fn waybar() {
+    let keeper = workday();
+    assert_eq!(
+        BarState::new(&keeper).format(Bar::Waybar),
+        r#"{"text":"1 overdue · 14:00 write docs","tooltip":"[x] 9:00 standup\n[!] 9:00 email triage\n[x] 11:00 review PR\n[x] 13:00 lunch\n[ ] 14:00 write docs\n[ ] 17:00 gym","class":"overdue"}"#
+    );
+
+    utc();
+    let empty = Keeper::default();
+    assert_eq!(
+        BarState::new(&empty).format(Bar::Waybar),
+        r#"{"text":"nothing left","tooltip":"Nothing planned today","class":"done"}"#
+    );
+}
+
+#[test]
+fn next_on_a_later_day() {
+    let mut keeper = workday();
+    keeper.set_clock(Clock::Fixed(at("2024-02-01T18:30:00Z")));
+    assert_eq!(
+        BarState::new(&keeper).format(Bar::Plain),
+        "3 overdue · Fri 10:00 dentist"
+    );
+}
+
+#[test]
+fn skips_blocked() {
+    let mut keeper = blocked();
+    keeper.set_clock(Clock::Fixed(at("2024-02-01T16:30:00Z")));
+    let state = BarState::new(&keeper);
+    // The review is late, and the deploy is waiting on it
+    assert_eq!(state.overdue, 3);
+    assert_eq!(state.next.unwrap().desc, "update status page");
+}
+
+#[test]
+fn deadlines_count() {
+    let keeper = deadlined();
+    // Expenses were due at noon, even though they're planned for later
+    let state = BarState::new(&keeper);
+    assert_eq!(state.overdue, 2);
+    // Its hour has passed, but it isn't due until tomorrow
+    assert_eq!(state.next.unwrap().desc, "draft report");
+}
+
+#[test]
+fn escapes_markup() {
+    let mut keeper = Keeper::default();
+    utc();
+    keeper.set_clock(Clock::Fixed(at("2024-02-01T13:30:00Z")));
+    add(&mut keeper, date("2024-02-01"), 14, "fix <br> & #12", false);
+    let state = BarState::new(&keeper);
+    assert_eq!(
+        state.format(Bar::I3blocks),
+        "14:00 fix &lt;br&gt; &amp; #12"
+    );
+    assert_eq!(state.format(Bar::Tmux), "14:00 fix <br> & ##12");
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use chrono::{DateTime, Local, TimeZone};
+use common::{date, nested, shown};
+use keeper_todo::{cli::ShowSet, data::Task};
+
+fn now() -> DateTime<Local> {
+    Local.with_ymd_and_hms(2024, 2, 1, 13, 30, 0).unwrap()
+}
+
+fn task(desc: &str) -> Task {
+    Task::new(desc.to_string())
+}
+
+/// ship release
+///     write changelog
+///     tag release
+///         push tag
+///         announce
+fn release() -> Task {
+    let mut tag = task("tag release");
+    tag.add_subtask(task("push tag"));
+    tag.add_subtask(task("announce"));
+    let mut release = task("ship release");
+    release.add_subtask(task("write changelog"));
+    release.add_subtask(tag);
+    release
+}
+
+#[test]
+fn progress_counts_every_level() {
+    let mut release = release();
+    assert_eq!(release.subtask_progress(), Some((0, 4)));
+    assert_eq!(
+        release.mark_path(&[1, 0], now()).as_deref(),
+        Some("push tag")
+    );
+    assert_eq!(release.subtask_progress(), Some((1, 4)));
+    assert_eq!(task("leaf").subtask_progress(), None);
+}
+
+#[test]
+fn parents_complete_with_their_children() {
+    let mut release = release();
+    release.mark_path(&[1, 0], now());
+    assert!(!release.subtasks()[1].completed());
+    release.mark_path(&[1, 1], now());
+    assert!(release.subtasks()[1].completed());
+    assert!(!release.completed());
+    release.mark_path(&[0], now());
+    assert!(release.completed());
+    assert_eq!(release.subtask_progress(), Some((4, 4)));
+}
+
+#[test]
+fn marking_a_parent_marks_its_children() {
+    let mut release = release();
+    assert_eq!(
+        release.mark_path(&[1], now()).as_deref(),
+        Some("tag release")
+    );
+    assert!(release.subtasks()[1].subtasks().iter().all(Task::completed));
+    assert!(!release.completed());
+    assert_eq!(release.subtask_progress(), Some((3, 4)));
+}
+
+#[test]
+fn missing_subtask() {
+    let mut release = release();
+    assert_eq!(release.mark_path(&[5], now()), None);
+    assert_eq!(release.mark_path(&[0, 0], now()), None);
+    assert_eq!(release.subtask_progress(), Some((0, 4)));
+}
+
+#[test]
+fn new_subtasks_reopen_the_parent() {
+    let mut release = release();
+    release.mark_complete(now());
+    assert!(release.completed());
+    release.add_subtask(task("write blog post"));
+    assert!(!release.completed());
+    assert_eq!(release.subtask_progress(), Some((4, 5)));
+}
+
+#[test]
+fn shown_as_a_tree() {
+    let shown = shown(&nested(), ShowSet::Date(date("2024-02-01")), false);
+    assert!(shown.contains(
+        "\
+[15] (ship release [2/5])
+    15.0.0 (write changelog)
+    15.0.1 (bump version)
+    15.0.2 (tag release [1/2])
+        15.0.2.0 (push tag)
+        15.0.2.1 (announce)
+[17] (gym)
+"
+    ));
+}
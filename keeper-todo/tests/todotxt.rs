@@ -0,0 +1,95 @@
+mod common;
+
+use common::{assert_text_snapshot, at, date, mixed, utc};
+use keeper_todo::{
+    data::{Keeper, Priority},
+    todotxt,
+};
+use keeper_util::clock::Clock;
+
+fn empty() -> Keeper {
+    utc();
+    let mut keeper = Keeper::default();
+    keeper.set_clock(Clock::Fixed(at("2024-02-01T13:30:00Z")));
+    keeper
+}
+
+#[test]
+fn export_todotxt() {
+    let keeper = mixed();
+    assert_text_snapshot("todo", &todotxt::export(&keeper, None, None));
+}
+
+#[test]
+fn todotxt_round_trip() {
+    let keeper = mixed();
+    let exported = todotxt::export(&keeper, None, None);
+    let mut imported = empty();
+    let tasks = todotxt::import(&exported, imported.clock().now()).unwrap();
+    assert_eq!(imported.import(tasks), (11, 0));
+    assert_eq!(todotxt::export(&imported, None, None), exported);
+}
+
+#[test]
+fn todotxt_merges() {
+    let mut keeper = mixed();
+    let exported = todotxt::export(&keeper, None, None);
+    let modified = keeper.task(date("2024-02-01"), 16, 0).unwrap().modified();
+
+    let edited = exported.replace(
+        "2024-02-01 gym t:2024-02-01 time:17:00",
+        "x 2024-02-01 2024-02-01 gym t:2024-02-01 time:17:00",
+    ) + "(B) 2024-01-31 call plumber @phone t:2024-02-02\n";
+    let tasks = todotxt::import(&edited, keeper.clock().now()).unwrap();
+    assert_eq!(keeper.import(tasks), (1, 11));
+
+    // Notes and subtasks that todo.txt doesn't have stay put
+    let trip = keeper.task(date("2024-02-01"), 16, 0).unwrap();
+    assert_eq!(trip.details().links, ["https://example.com/flights"]);
+    assert_eq!(trip.modified(), modified);
+    let release = keeper.task(date("2024-02-01"), 15, 0).unwrap();
+    assert_eq!(release.subtasks().len(), 3);
+
+    let gym = keeper.task(date("2024-02-01"), 17, 0).unwrap();
+    assert!(gym.completed());
+    assert_eq!(gym.completed_at(), Some(at("2024-02-01T17:59:59Z")));
+    let plumber = keeper.task(date("2024-02-02"), 9, 0).unwrap();
+    assert_eq!(plumber.desc(), "call plumber");
+    assert_eq!(plumber.priority(), Priority::High);
+    assert_eq!(plumber.tags(), ["@phone"]);
+}
+
+#[test]
+fn todotxt_from_elsewhere() {
+    let todo = "\
+(A) Thank Mom for the meatballs @phone
+x 2024-01-30 2024-01-28 Review budget +Finances pri:B
+2024-01-29 Pay rent due:2024-02-01 +Home
+
+Post signs around the neighborhood +GarageSale t:2024-02-03 time:8:30
+";
+    let mut keeper = empty();
+    let tasks = todotxt::import(todo, keeper.clock().now()).unwrap();
+    assert_eq!(keeper.import(tasks), (4, 0));
+
+    let mom = &keeper.inbox[0];
+    assert_eq!(mom.desc(), "Thank Mom for the meatballs");
+    assert_eq!(mom.priority(), Priority::Urgent);
+    let budget = keeper.inbox.iter().find(|t| t.completed()).unwrap();
+    assert_eq!(budget.priority(), Priority::High);
+    assert_eq!(budget.created(), Some(at("2024-01-28T00:00:00Z")));
+    assert_eq!(budget.completed_at(), Some(at("2024-01-30T12:00:00Z")));
+    let rent = keeper
+        .inbox
+        .iter()
+        .find(|t| t.desc() == "Pay rent")
+        .unwrap();
+    assert_eq!(rent.deadline().unwrap().date, date("2024-02-01"));
+    assert_eq!(rent.tags(), ["+Home"]);
+
+    let signs = keeper.task(date("2024-02-03"), 8, 0).unwrap();
+    assert_eq!(signs.desc(), "Post signs around the neighborhood");
+
+    assert!(todotxt::import("(A) +tag-only", keeper.clock().now()).is_err());
+    assert!(todotxt::import("call t:someday", keeper.clock().now()).is_err());
+}
@@ -0,0 +1,170 @@
+mod common;
+
+use common::{assert_text_snapshot, date, nested, workday};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use keeper_todo::{
+    data::{Keeper, Priority},
+    tui::App,
+};
+use ratatui::{backend::TestBackend, Terminal};
+
+fn press(app: &mut App, keys: &str) {
+    for c in keys.chars() {
+        app.handle(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+    }
+}
+
+fn enter(app: &mut App) {
+    app.handle(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+}
+
+fn screen(app: &App) -> String {
+    let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+    terminal.draw(|frame| app.draw(frame)).unwrap();
+    let buffer = terminal.backend().buffer();
+    let mut screen = String::new();
+    for y in 0..buffer.area.height {
+        let line: String = (0..buffer.area.width)
+            .map(|x| buffer.get(x, y).symbol())
+            .collect();
+        screen.push_str(line.trim_end());
+        screen.push('\n');
+    }
+    screen
+}
+
+fn ready(mut keeper: Keeper) -> Keeper {
+    keeper.assign_ids();
+    keeper
+}
+
+fn descs(keeper: &Keeper, day: &str, hour: usize) -> Vec<String> {
+    keeper
+        .days
+        .get(&date(day))
+        .and_then(|schedule| schedule.timeslots.get(&hour))
+        .map(|tasks| tasks.iter().map(|task| task.desc().to_string()).collect())
+        .unwrap_or_default()
+}
+
+#[test]
+fn day_and_week() {
+    let mut keeper = ready(workday());
+    let mut app = App::new(&mut keeper);
+    // It's 13:30, so lunch is selected
+    assert_text_snapshot("tui_day", &screen(&app));
+    press(&mut app, "w");
+    assert_text_snapshot("tui_week", &screen(&app));
+}
+
+#[test]
+fn toggle() {
+    let mut keeper = ready(workday());
+    let mut app = App::new(&mut keeper);
+    // Down from lunch to write docs
+    press(&mut app, "j ");
+    press(&mut app, "k j ");
+    assert_eq!(
+        app.changes(),
+        [
+            "mark 'write docs' done",
+            "unmark 'lunch'",
+            "unmark 'write docs'"
+        ]
+    );
+    drop(app);
+    let docs = keeper.task(date("2024-02-01"), 14, 0).unwrap();
+    assert!(!docs.completed());
+    let lunch = keeper.task(date("2024-02-01"), 13, 0).unwrap();
+    assert!(!lunch.completed());
+}
+
+#[test]
+fn moves() {
+    let mut keeper = ready(workday());
+    let mut app = App::new(&mut keeper);
+    // Write docs an hour later, then to tomorrow
+    press(&mut app, "jJL");
+    assert_eq!(
+        app.changes(),
+        [
+            "move 'write docs' to 15:00 on 01 Feb",
+            "move 'write docs' to 15:00 on 02 Feb"
+        ]
+    );
+    // Still selected on the next day
+    press(&mut app, " ");
+    drop(app);
+    assert!(descs(&keeper, "2024-02-01", 14).is_empty());
+    let docs = keeper.task(date("2024-02-02"), 15, 0).unwrap();
+    assert_eq!(docs.desc(), "write docs");
+    assert_eq!(docs.deferred(), 2);
+    assert!(docs.completed());
+}
+
+#[test]
+fn moves_stop_at_midnight() {
+    let mut keeper = ready(workday());
+    let mut app = App::new(&mut keeper);
+    // Lunch from 13:00 to 23:00, and then no further
+    press(&mut app, &"J".repeat(11));
+    assert_eq!(app.changes().len(), 10);
+    assert!(screen(&app).contains("can't move past midnight"));
+    drop(app);
+    assert_eq!(descs(&keeper, "2024-02-01", 23), ["lunch"]);
+}
+
+#[test]
+fn add_and_edit() {
+    let mut keeper = ready(workday());
+    let mut app = App::new(&mut keeper);
+    press(&mut app, "a15 call mom !high +family");
+    enter(&mut app);
+    // The new task is selected, so the subtask goes under it
+    press(&mut app, "Abuy flowers");
+    enter(&mut app);
+    press(&mut app, "k");
+    press(&mut app, "e");
+    app.handle(KeyEvent::new(KeyCode::Backspace, KeyModifiers::NONE));
+    press(&mut app, "ther");
+    enter(&mut app);
+    assert_eq!(
+        app.changes(),
+        [
+            "add 'call mom' at 15:00 on 01 Feb",
+            "add 'buy flowers' under 'call mom'",
+            "edit 'call mom' to 'call mother'"
+        ]
+    );
+    drop(app);
+    let call = keeper.task(date("2024-02-01"), 15, 0).unwrap();
+    assert_eq!(call.desc(), "call mother");
+    assert_eq!(call.priority(), Priority::High);
+    assert_eq!(call.tags(), ["+family"]);
+    assert_eq!(call.subtasks()[0].desc(), "buy flowers");
+}
+
+#[test]
+fn delete_asks_first() {
+    let mut keeper = ready(workday());
+    let mut app = App::new(&mut keeper);
+    press(&mut app, "dn");
+    assert!(app.changes().is_empty());
+    press(&mut app, "dy");
+    assert_eq!(app.changes(), ["delete 'lunch'"]);
+    assert!(!app.quit());
+    press(&mut app, "q");
+    assert!(app.quit());
+    drop(app);
+    assert!(descs(&keeper, "2024-02-01", 13).is_empty());
+}
+
+#[test]
+fn subtasks_stay_put() {
+    let mut keeper = ready(nested());
+    let mut app = App::new(&mut keeper);
+    // Past write docs and the release, to its first subtask
+    press(&mut app, "jjjJ");
+    assert!(app.changes().is_empty());
+    assert!(screen(&app).contains("subtasks move with their parent"));
+}
@@ -0,0 +1,20 @@
+use keeper_todo::wallpaper::Monitor;
+
+fn monitor(name: &str, width: u32, height: u32) -> Monitor {
+    Monitor {
+        name: Some(name.to_string()),
+        width,
+        height,
+        days: None,
+    }
+}
+
+#[test]
+fn skips_tiny_monitors() {
+    let configured = [
+        monitor("DP-1", 2560, 1440),
+        monitor("HDMI-1", 0, 0),
+        monitor("DP-2", 40, 900),
+    ];
+    assert_eq!(Monitor::resolve(&configured), [monitor("DP-1", 2560, 1440)]);
+}
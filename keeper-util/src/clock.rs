@@ -0,0 +1,52 @@
+use std::env;
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone};
+
+use crate::fatal;
+
+/// Where the current time comes from. Everything that cares about "now" (what
+/// `today` means, which tasks are overdue, ...) asks a `Clock`, so that it can
+/// be pinned for tests or with `KEEPER_NOW`.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum Clock {
+    #[default]
+    System,
+    Fixed(DateTime<Local>),
+}
+
+impl Clock {
+    /// The system clock, unless `KEEPER_NOW` is set.
+    pub fn from_env() -> Self {
+        let Ok(now) = env::var("KEEPER_NOW") else {
+            return Self::System;
+        };
+        match parse_now(&now) {
+            Some(now) => Self::Fixed(now),
+            None => fatal!(
+                "failed to parse KEEPER_NOW [{now}], expecting RFC 3339 or [%Y-%m-%d %H:%M]"
+            ),
+        }
+    }
+
+    pub fn now(&self) -> DateTime<Local> {
+        match self {
+            Self::System => Local::now(),
+            Self::Fixed(now) => *now,
+        }
+    }
+
+    pub fn today(&self) -> NaiveDate {
+        self.now().date_naive()
+    }
+}
+
+/// Parses an RFC 3339 timestamp, or a local time like `2024-02-01 14:30`.
+pub fn parse_now(s: &str) -> Option<DateTime<Local>> {
+    if let Ok(now) = DateTime::parse_from_rfc3339(s) {
+        return Some(now.with_timezone(&Local));
+    }
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(s, format).ok())
+        .and_then(|now| Local.from_local_datetime(&now).earliest())
+}
@@ -1,3 +1,4 @@
+pub mod clock;
 pub mod color;
 
 #[macro_export]
@@ -16,7 +17,17 @@ macro_rules! fatal {
         {
             print!("{}ERROR{} ", $crate::color::RED, $crate::color::RESET);
             println!($($e),+);
-            ::std::process::exit(1);
+            $crate::exit(1);
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! warn {
+    ($($e:expr),+ $(,)?) => {
+        {
+            print!("{}WARN{} ", $crate::color::YELLOW, $crate::color::RESET);
+            println!($($e),+);
         }
     };
 }
@@ -31,15 +42,45 @@ macro_rules! info {
     };
 }
 
-pub fn parse_date(s: &str) -> NaiveDate {
+thread_local! {
+    static RECOVERING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// What [`exit`] unwinds with inside [`recover`].
+struct Exit;
+
+/// Exits the process, or unwinds back to [`recover`] if inside one. `fatal!`
+/// goes through this.
+pub fn exit(code: i32) -> ! {
+    if RECOVERING.get() {
+        // Not `panic!`, which would print a panic message too
+        panic::resume_unwind(Box::new(Exit));
+    }
+    process::exit(code)
+}
+
+/// Runs `f`, returning `None` instead of exiting if it hits a `fatal!` (which
+/// has already printed its error), so many commands can run in one process.
+pub fn recover<T>(f: impl FnOnce() -> T) -> Option<T> {
+    let outer = RECOVERING.replace(true);
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    RECOVERING.set(outer);
+    match result {
+        Ok(value) => Some(value),
+        Err(payload) if payload.is::<Exit>() => None,
+        Err(payload) => panic::resume_unwind(payload),
+    }
+}
+
+pub fn parse_date(s: &str, clock: Clock) -> NaiveDate {
     if s == "today" {
-        return Local::now().date_naive();
+        return clock.today();
     }
     if s == "tomorrow" {
-        return Local::now().date_naive() + Days::new(1);
+        return clock.today() + Days::new(1);
     }
     if s == "yesterday" {
-        return Local::now().date_naive() - Days::new(1);
+        return clock.today() - Days::new(1);
     }
     NaiveDate::parse_from_str(s, "%d-%m-%Y").unwrap_or_else(|e| {
         fatal!("failed to parse date: {e}");
@@ -51,14 +92,17 @@ pub fn current_version() -> &'static str {
 }
 
 use std::{
+    cell::Cell,
     fs::{self, DirBuilder, File},
     marker::PhantomData,
+    panic::{self, AssertUnwindSafe},
     path::{Path, PathBuf},
     process::{self, Stdio},
 };
 
 use anyhow::{anyhow, ensure, Context};
-use chrono::{NaiveDate, Local, Days};
+use chrono::{NaiveDate, Days};
+use clock::Clock;
 use serde::{Deserialize, Serialize};
 
 pub struct DataManager<T> {
@@ -79,10 +123,14 @@ where
         })
     }
 
-    fn data_dir(&self) -> &Path {
+    pub fn data_dir(&self) -> &Path {
         self.data_path.parent().unwrap()
     }
 
+    pub fn data_filename(&self) -> &Path {
+        Path::new(self.data_path.file_name().unwrap())
+    }
+
     fn data_dir_str(&self) -> &str {
         self.data_dir().to_str().unwrap()
     }